@@ -0,0 +1,469 @@
+use crate::error::AppError;
+use crate::timestamp::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::{Channels, SampleBuffer, SignalSpec};
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Whisper works on 16kHz mono audio
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Which input channels end up in the mono signal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelSelection {
+    /// Average every channel except LFE
+    #[default]
+    Mix,
+    /// Use a single channel, counting from 0 (0 is left for stereo)
+    Index(usize),
+}
+
+/// Decodes an audio file incrementally into 16kHz mono samples
+pub struct AudioReader {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+    total_frames: Option<u64>,
+    channel: ChannelSelection,
+    /// Interleaved copy of the current packet, with the spec it was sized for
+    sample_buf: Option<(SampleBuffer<f32>, SignalSpec)>,
+    resampler: Resampler,
+    /// Converted samples decoded but not yet handed out
+    pending: Vec<f32>,
+    finished: bool,
+}
+
+impl AudioReader {
+    pub fn open(path: &Path, channel: ChannelSelection) -> Result<Self, AppError> {
+        eprintln!("Opening audio file: {}", path.display());
+        // Open the media source
+        let file = File::open(path)
+            .map_err(|e| AppError::WhisperError(format!("Failed to open audio file: {}", e)))?;
+
+        // Create a media source stream
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+        // Create a hint to help the format registry guess what format the media is
+        let mut hint = Hint::new();
+        if let Some(ext_str) = path.extension().and_then(|ext| ext.to_str()) {
+            hint.with_extension(ext_str);
+        }
+
+        // Probe the media source
+        let probed = symphonia::default::get_probe()
+            .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|e| AppError::WhisperError(format!("Failed to probe media format: {}", e)))?;
+
+        let format = probed.format;
+
+        // Find the first audio track
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| AppError::WhisperError("No audio track found".to_string()))?;
+
+        // Create a decoder for the track
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|e| AppError::WhisperError(format!("Failed to create decoder: {}", e)))?;
+
+        let track_id = track.id;
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(WHISPER_SAMPLE_RATE);
+        let total_frames = track.codec_params.n_frames;
+
+        // Catch a bad channel choice up front when the container tells us the layout;
+        // otherwise it is checked against each decoded packet
+        if let (ChannelSelection::Index(index), Some(channels)) = (channel, track.codec_params.channels) {
+            check_channel_index(index, channels.count())?;
+        }
+
+        if sample_rate != WHISPER_SAMPLE_RATE {
+            eprintln!("Resampling from {}Hz to {}Hz", sample_rate, WHISPER_SAMPLE_RATE);
+        }
+
+        Ok(AudioReader {
+            format,
+            decoder,
+            track_id,
+            sample_rate,
+            total_frames,
+            channel,
+            sample_buf: None,
+            resampler: Resampler::new(sample_rate, WHISPER_SAMPLE_RATE),
+            pending: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// Duration of the track, if the container reports it
+    pub fn duration(&self) -> Option<Timestamp> {
+        self.total_frames
+            .map(|frames| Timestamp::from_secs(frames as f64 / self.sample_rate as f64))
+    }
+
+    /// True once every sample has been handed out
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.pending.is_empty()
+    }
+
+    /// Append up to `max` samples to `out`, returning how many were added
+    ///
+    /// Returns fewer than `max` only at the end of the stream.
+    pub fn read(&mut self, out: &mut Vec<f32>, max: usize) -> Result<usize, AppError> {
+        while self.pending.len() < max && !self.finished {
+            self.decode_next_packet()?;
+        }
+
+        let n = self.pending.len().min(max);
+        out.extend(self.pending.drain(..n));
+        Ok(n)
+    }
+
+    /// Decode one packet into `pending`, setting `finished` at the end of the stream
+    fn decode_next_packet(&mut self) -> Result<(), AppError> {
+        // Get the next packet from the format reader
+        let packet = match self.format.next_packet() {
+            Ok(packet) => packet,
+            Err(symphonia::core::errors::Error::IoError(ref e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                self.finished = true;
+                self.resampler.flush(&mut self.pending);
+                return Ok(());
+            }
+            Err(e) => return Err(AppError::WhisperError(format!("Error reading packet: {}", e))),
+        };
+
+        // Skip packets from other tracks
+        if packet.track_id() != self.track_id {
+            return Ok(());
+        }
+
+        // Decode the packet
+        let decoded = match self.decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                eprintln!("Error decoding packet: {}", e);
+                return Ok(());
+            }
+        };
+
+        let spec = *decoded.spec();
+        let frames = decoded.frames();
+        if spec.channels.count() == 0 || frames == 0 {
+            return Ok(());
+        }
+
+        // (Re)create the sample buffer when the layout changes or a packet is larger than any seen so far
+        let reusable = match &self.sample_buf {
+            Some((buf, buf_spec)) => *buf_spec == spec && buf.capacity() >= frames * spec.channels.count(),
+            None => false,
+        };
+        if !reusable {
+            self.sample_buf = Some((SampleBuffer::<f32>::new(decoded.capacity().max(frames) as u64, spec), spec));
+        }
+
+        if let Some((buf, _)) = &mut self.sample_buf {
+            buf.copy_interleaved_ref(decoded);
+            let mono = downmix(buf.samples(), spec.channels, self.channel)?;
+            self.resampler.process(&mono, &mut self.pending);
+        }
+
+        Ok(())
+    }
+}
+
+/// Load a whole audio file as 16kHz mono samples
+pub fn load_audio_file(path: &Path, channel: ChannelSelection) -> Result<Vec<f32>, AppError> {
+    let mut reader = AudioReader::open(path, channel)?;
+    let mut audio_data = Vec::new();
+    reader.read(&mut audio_data, usize::MAX)?;
+
+    eprintln!("Loaded audio data size: {} samples", audio_data.len());
+    Ok(audio_data)
+}
+
+fn check_channel_index(index: usize, count: usize) -> Result<(), AppError> {
+    if index >= count {
+        return Err(AppError::WhisperError(format!(
+            "Cannot use channel {}, the audio only has {} channel(s)",
+            index, count
+        )));
+    }
+    Ok(())
+}
+
+/// Reduce interleaved frames with the given channel layout to one mono sample per frame
+fn downmix(interleaved: &[f32], channels: Channels, selection: ChannelSelection) -> Result<Vec<f32>, AppError> {
+    let count = channels.count();
+    let frames = interleaved.chunks_exact(count);
+
+    match selection {
+        ChannelSelection::Index(index) => {
+            check_channel_index(index, count)?;
+            Ok(frames.map(|frame| frame[index]).collect())
+        }
+        ChannelSelection::Mix => {
+            // The LFE channel carries no speech and only adds rumble to the average
+            let weights: Vec<f32> = channels
+                .iter()
+                .map(|c| if c == Channels::LFE1 || c == Channels::LFE2 { 0.0 } else { 1.0 })
+                .collect();
+            let total: f32 = weights.iter().sum();
+            // A stream that is nothing but LFE is still better than silence
+            let (weights, total) = if total > 0.0 { (weights, total) } else { (vec![1.0; count], count as f32) };
+
+            Ok(frames
+                .map(|frame| frame.iter().zip(&weights).map(|(s, w)| s * w).sum::<f32>() / total)
+                .collect())
+        }
+    }
+}
+
+/// Zero crossings of the sinc kept on each side of the kernel centre
+const KERNEL_ZERO_CROSSINGS: usize = 24;
+/// Table entries per zero crossing, the kernel is linearly interpolated between them
+const KERNEL_RESOLUTION: usize = 512;
+/// Kaiser window shape, about 80dB of stopband attenuation
+const KAISER_BETA: f64 = 8.0;
+/// Fraction of the lower Nyquist frequency kept, the rest is the filter's transition band
+const CUTOFF: f64 = 0.9;
+
+/// Streaming band-limited sample rate converter
+///
+/// Each output sample is a Kaiser-windowed sinc interpolation of the input. When
+/// downsampling the sinc is stretched so it also low-pass filters everything
+/// above the output Nyquist frequency, which would otherwise alias.
+struct Resampler {
+    /// Input samples advanced per output sample
+    step: f64,
+    /// Cutoff as a fraction of the input Nyquist frequency
+    cutoff: f64,
+    /// Kernel half-width in input samples
+    half_width: f64,
+    /// One side of the windowed sinc, indexed in zero-crossing units
+    kernel: Vec<f32>,
+    /// Input samples still needed by upcoming output samples
+    history: Vec<f32>,
+    /// Absolute input index of `history[0]`
+    history_start: u64,
+    /// Total input samples received
+    consumed: u64,
+    /// Total output samples produced
+    produced: u64,
+}
+
+impl Resampler {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        let ratio = to_rate as f64 / from_rate as f64;
+        let cutoff = CUTOFF * ratio.min(1.0);
+
+        let kernel_len = KERNEL_ZERO_CROSSINGS * KERNEL_RESOLUTION + 1;
+        let kernel = (0..kernel_len)
+            .map(|i| {
+                let x = i as f64 / KERNEL_RESOLUTION as f64;
+                let u = x / KERNEL_ZERO_CROSSINGS as f64;
+                (sinc(x) * kaiser(u, KAISER_BETA)) as f32
+            })
+            .collect();
+
+        Resampler {
+            step: 1.0 / ratio,
+            cutoff,
+            half_width: KERNEL_ZERO_CROSSINGS as f64 / cutoff,
+            kernel,
+            history: Vec::new(),
+            history_start: 0,
+            consumed: 0,
+            produced: 0,
+        }
+    }
+
+    fn is_passthrough(&self) -> bool {
+        self.step == 1.0
+    }
+
+    /// Feed input samples, appending every output sample that can be computed so far
+    fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        self.consumed += input.len() as u64;
+        if self.is_passthrough() {
+            out.extend_from_slice(input);
+            self.produced = self.consumed;
+            return;
+        }
+
+        self.history.extend_from_slice(input);
+        self.drain(out, self.consumed as f64 - self.half_width);
+    }
+
+    /// Emit the remaining output samples at the end of the stream
+    fn flush(&mut self, out: &mut Vec<f32>) {
+        if self.is_passthrough() {
+            return;
+        }
+        self.drain(out, self.consumed as f64);
+        self.history.clear();
+    }
+
+    /// Produce output samples whose centre lies before `limit`, in input samples
+    fn drain(&mut self, out: &mut Vec<f32>, limit: f64) {
+        loop {
+            let centre = self.produced as f64 * self.step;
+            if centre >= limit {
+                break;
+            }
+            out.push(self.interpolate(centre));
+            self.produced += 1;
+        }
+
+        // Forget input that no future output sample can reach
+        let next_centre = self.produced as f64 * self.step;
+        let keep_from = ((next_centre - self.half_width).floor().max(0.0) as u64).max(self.history_start);
+        let drop = ((keep_from - self.history_start) as usize).min(self.history.len());
+        self.history.drain(..drop);
+        self.history_start += drop as u64;
+    }
+
+    fn interpolate(&self, centre: f64) -> f32 {
+        let first = (centre - self.half_width).ceil().max(self.history_start as f64) as u64;
+        let last = ((centre + self.half_width).floor() as u64).min(self.consumed.saturating_sub(1));
+
+        let mut acc = 0.0f64;
+        for index in first..=last {
+            let sample = self.history[(index - self.history_start) as usize];
+            let x = (index as f64 - centre).abs() * self.cutoff * KERNEL_RESOLUTION as f64;
+            let i = x as usize;
+            if i + 1 >= self.kernel.len() {
+                continue;
+            }
+            let frac = (x - i as f64) as f32;
+            let weight = self.kernel[i] + (self.kernel[i + 1] - self.kernel[i]) * frac;
+            acc += (sample * weight) as f64;
+        }
+
+        (acc * self.cutoff) as f32
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// Kaiser window at `u` in [-1, 1]
+fn kaiser(u: f64, beta: f64) -> f64 {
+    bessel_i0(beta * (1.0 - u * u).max(0.0).sqrt()) / bessel_i0(beta)
+}
+
+/// Zeroth-order modified Bessel function of the first kind
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..50 {
+        term *= (half / k as f64) * (half / k as f64);
+        sum += term;
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn tone(freq: f64, rate: u32, secs: f64) -> Vec<f32> {
+        let n = (rate as f64 * secs) as usize;
+        (0..n)
+            .map(|i| (0.5 * (2.0 * PI * freq * i as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    fn resample(input: &[f32], from: u32, to: u32, block: usize) -> Vec<f32> {
+        let mut resampler = Resampler::new(from, to);
+        let mut out = Vec::new();
+        for chunk in input.chunks(block) {
+            resampler.process(chunk, &mut out);
+        }
+        resampler.flush(&mut out);
+        out
+    }
+
+    /// RMS level in dB relative to the 0.5 amplitude test tone, ignoring filter edges
+    fn level_db(samples: &[f32]) -> f64 {
+        let body = &samples[samples.len() / 10..samples.len() * 9 / 10];
+        let rms = (body.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / body.len() as f64).sqrt();
+        20.0 * (rms / (0.5 / 2f64.sqrt())).log10()
+    }
+
+    #[test]
+    fn downmix_averages_or_selects_channels() {
+        let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+        let frames = [0.2, 0.6, -0.4, 0.0];
+
+        assert_eq!(downmix(&frames, stereo, ChannelSelection::Mix).unwrap(), vec![0.4, -0.2]);
+        assert_eq!(downmix(&frames, stereo, ChannelSelection::Index(0)).unwrap(), vec![0.2, -0.4]);
+        assert_eq!(downmix(&frames, stereo, ChannelSelection::Index(1)).unwrap(), vec![0.6, 0.0]);
+        assert!(matches!(
+            downmix(&frames, stereo, ChannelSelection::Index(2)),
+            Err(AppError::WhisperError(_))
+        ));
+    }
+
+    #[test]
+    fn downmix_leaves_out_lfe() {
+        let layout = Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::LFE1;
+        let frames = [0.3, 0.1, 1.0];
+        assert_eq!(downmix(&frames, layout, ChannelSelection::Mix).unwrap(), vec![0.2]);
+        assert_eq!(downmix(&[1.0], Channels::LFE1, ChannelSelection::Mix).unwrap(), vec![1.0]);
+    }
+
+    #[test]
+    fn tones_above_output_nyquist_do_not_alias() {
+        // Nearest-sample picking folds these straight back into the speech band at full level
+        for (from, freq) in [(48000, 10000.0), (48000, 13000.0), (44100, 12000.0), (44100, 9000.0)] {
+            let out = resample(&tone(freq, from, 1.0), from, WHISPER_SAMPLE_RATE, 4096);
+            let level = level_db(&out);
+            assert!(level < -60.0, "{}Hz from {}Hz aliased at {:.1}dB", freq, from, level);
+        }
+    }
+
+    #[test]
+    fn passband_tones_are_preserved() {
+        for (from, freq) in [(48000, 440.0), (44100, 3000.0), (22050, 6000.0), (8000, 1000.0)] {
+            let out = resample(&tone(freq, from, 1.0), from, WHISPER_SAMPLE_RATE, 1000);
+            let level = level_db(&out);
+            assert!(level.abs() < 0.1, "{}Hz from {}Hz came out at {:.2}dB", freq, from, level);
+
+            let expected = tone(freq, WHISPER_SAMPLE_RATE, 1.0);
+            let mid = out.len() / 2;
+            for i in mid..mid + 100 {
+                assert!((out[i] - expected[i]).abs() < 0.01, "{}Hz from {}Hz differs at {}", freq, from, i);
+            }
+        }
+    }
+
+    #[test]
+    fn output_length_and_block_size_independence() {
+        let input = tone(1000.0, 44100, 2.0);
+        let whole = resample(&input, 44100, WHISPER_SAMPLE_RATE, input.len());
+        let blocks = resample(&input, 44100, WHISPER_SAMPLE_RATE, 333);
+
+        assert_eq!(whole.len(), 32000);
+        assert_eq!(whole, blocks);
+        assert_eq!(resample(&input, 16000, WHISPER_SAMPLE_RATE, 100), input);
+    }
+}
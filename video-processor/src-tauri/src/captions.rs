@@ -0,0 +1,313 @@
+use crate::subtitles::Cue;
+use crate::timestamp::Timestamp;
+use crate::whisper::Transcript;
+use serde::{Deserialize, Serialize};
+
+/// A pause this long between words always starts a new cue
+const PAUSE_BREAK_SECS: f64 = 1.5;
+
+/// Readability limits for caption cues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CaptionOptions {
+    pub max_chars_per_line: usize,
+    pub max_lines: usize,
+    /// Reading speed limit; faster cues are held on screen longer where the gap allows
+    pub max_chars_per_second: f64,
+    pub min_duration_secs: f64,
+    pub max_duration_secs: f64,
+}
+
+impl Default for CaptionOptions {
+    fn default() -> Self {
+        CaptionOptions {
+            max_chars_per_line: 42,
+            max_lines: 2,
+            max_chars_per_second: 17.0,
+            min_duration_secs: 1.0,
+            max_duration_secs: 7.0,
+        }
+    }
+}
+
+/// A word with its timing, either from Whisper or spread evenly over its segment
+#[derive(Debug, Clone)]
+struct TimedWord {
+    text: String,
+    start: Timestamp,
+    end: Timestamp,
+}
+
+impl TimedWord {
+    fn ends_sentence(&self) -> bool {
+        matches!(trailing_punctuation(&self.text), Some('.' | '?' | '!' | '…'))
+    }
+
+    fn ends_clause(&self) -> bool {
+        matches!(trailing_punctuation(&self.text), Some(',' | ';' | ':' | '–' | '—'))
+    }
+}
+
+/// The last character of a word, looking past closing quotes and brackets
+fn trailing_punctuation(text: &str) -> Option<char> {
+    text.trim_end_matches(['"', '\'', '”', '’', ')', ']']).chars().last()
+}
+
+/// Re-split a transcript into caption cues that respect `options`
+pub fn segment_captions(transcript: &Transcript, options: &CaptionOptions) -> Vec<Cue> {
+    let max_chars_per_line = options.max_chars_per_line.max(1);
+    let max_lines = options.max_lines.max(1);
+    let words = timed_words(transcript);
+
+    let mut groups: Vec<Vec<TimedWord>> = Vec::new();
+    let mut current: Vec<TimedWord> = Vec::new();
+
+    let capacity = max_chars_per_line * max_lines;
+    for word in words {
+        if current.last().is_some_and(|last| (word.start - last.end).as_secs() >= PAUSE_BREAK_SECS) {
+            groups.push(std::mem::take(&mut current));
+        }
+
+        // End cues until the word fits, since the words carried over may still be too many
+        while !current.is_empty() {
+            let too_long = (word.end - current[0].start).as_secs() > options.max_duration_secs;
+            let mut candidate: Vec<&str> = current.iter().map(|w| w.text.as_str()).collect();
+            candidate.push(&word.text);
+            let overflows = wrap(&candidate, max_chars_per_line).len() > max_lines;
+            if !too_long && !overflows {
+                break;
+            }
+
+            // Rather than cutting mid-phrase, end the cue at a late enough clause break
+            let carried = match clause_break(&current, capacity / 2) {
+                Some(index) => current.split_off(index),
+                None => Vec::new(),
+            };
+            groups.push(std::mem::replace(&mut current, carried));
+        }
+
+        let ends_sentence = word.ends_sentence();
+        current.push(word);
+        if ends_sentence {
+            groups.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+
+    let mut cues: Vec<Cue> = groups
+        .into_iter()
+        .filter(|group| !group.is_empty())
+        .map(|group| {
+            let texts: Vec<&str> = group.iter().map(|w| w.text.as_str()).collect();
+            Cue {
+                start: group[0].start,
+                end: group[group.len() - 1].end,
+                text: wrap(&texts, max_chars_per_line).join("\n"),
+            }
+        })
+        .collect();
+
+    adjust_timing(&mut cues, options);
+    cues
+}
+
+/// Index after the last clause-ending word, if the text before it is at least `min_chars` long
+fn clause_break(words: &[TimedWord], min_chars: usize) -> Option<usize> {
+    let mut chars = 0;
+    let mut best = None;
+    for (i, word) in words.iter().enumerate() {
+        chars += word.text.chars().count() + usize::from(i > 0);
+        if word.ends_clause() && chars >= min_chars && i + 1 < words.len() {
+            best = Some(i + 1);
+        }
+    }
+    best
+}
+
+/// Hold cues for the minimum duration and reading speed, without running into the next cue
+fn adjust_timing(cues: &mut [Cue], options: &CaptionOptions) {
+    for i in 0..cues.len() {
+        let next_start = cues.get(i + 1).map(|next| next.start);
+        let cue = &mut cues[i];
+
+        let chars = cue.text.chars().filter(|c| *c != '\n').count() as f64;
+        let reading_secs = if options.max_chars_per_second > 0.0 {
+            chars / options.max_chars_per_second
+        } else {
+            0.0
+        };
+        let wanted = reading_secs
+            .max(options.min_duration_secs)
+            .min(options.max_duration_secs.max(options.min_duration_secs));
+
+        if (cue.end - cue.start).as_secs() < wanted {
+            let mut end = cue.start + Timestamp::from_secs(wanted);
+            if let Some(next_start) = next_start {
+                if end > next_start {
+                    end = next_start;
+                }
+            }
+            if end > cue.end {
+                cue.end = end;
+            }
+        }
+    }
+}
+
+/// Break words into lines of at most `max_chars`, balancing two-line cues
+///
+/// A single word longer than a line gets a line to itself.
+fn wrap(words: &[&str], max_chars: usize) -> Vec<String> {
+    let len = |words: &[&str]| words.iter().map(|w| w.chars().count()).sum::<usize>() + words.len().saturating_sub(1);
+
+    let mut lines: Vec<Vec<&str>> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if len(line) + 1 + word.chars().count() <= max_chars => line.push(word),
+            _ => lines.push(vec![word]),
+        }
+    }
+
+    // Greedy filling leaves a long top line and a stub below; even the two lines out instead
+    if lines.len() == 2 {
+        let best = (1..words.len())
+            .filter(|&split| len(&words[..split]) <= max_chars && len(&words[split..]) <= max_chars)
+            .min_by_key(|&split| len(&words[..split]).abs_diff(len(&words[split..])));
+        if let Some(split) = best {
+            lines = vec![words[..split].to_vec(), words[split..].to_vec()];
+        }
+    }
+
+    lines.into_iter().map(|line| line.join(" ")).collect()
+}
+
+/// Every word of the transcript with timings, estimating them for segments without word timestamps
+fn timed_words(transcript: &Transcript) -> Vec<TimedWord> {
+    let mut words = Vec::new();
+    for segment in &transcript.segments {
+        if !segment.words.is_empty() {
+            words.extend(segment.words.iter().filter(|w| !w.text.trim().is_empty()).map(|w| TimedWord {
+                text: w.text.trim().to_string(),
+                start: w.start,
+                end: w.end,
+            }));
+            continue;
+        }
+
+        // Spread the segment's time over its words in proportion to their length
+        let texts: Vec<&str> = segment.text.split_whitespace().collect();
+        let total_chars: usize = texts.iter().map(|t| t.chars().count()).sum();
+        let duration = (segment.end - segment.start).as_secs().max(0.0);
+        let mut position = segment.start;
+        for text in texts {
+            let share = duration * text.chars().count() as f64 / total_chars.max(1) as f64;
+            let end = position + Timestamp::from_secs(share);
+            words.push(TimedWord {
+                text: text.to_string(),
+                start: position,
+                end,
+            });
+            position = end;
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whisper::{TranscriptSegment, TranscriptWord};
+
+    /// Words spoken back to back at 0.3s each
+    fn transcript(text: &str) -> Transcript {
+        let words: Vec<TranscriptWord> = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, w)| TranscriptWord {
+                text: w.to_string(),
+                start: Timestamp::from_secs(i as f64 * 0.3),
+                end: Timestamp::from_secs(i as f64 * 0.3 + 0.3),
+                probability: 1.0,
+            })
+            .collect();
+        let segment = TranscriptSegment {
+            start: Timestamp::ZERO,
+            end: words.last().map(|w| w.end).unwrap_or_default(),
+            text: format!(" {}", text),
+            words,
+        };
+        Transcript {
+            segments: vec![segment],
+            text: text.to_string(),
+            language: None,
+            language_probability: None,
+            translated: false,
+        }
+    }
+
+    #[test]
+    fn cues_fit_line_and_duration_limits() {
+        let text = "So today we are going to look at how the new render pipeline works and why \
+                    it is so much faster than the old one that we shipped last spring for everybody";
+        let options = CaptionOptions::default();
+        let cues = segment_captions(&transcript(text), &options);
+
+        assert!(cues.len() > 1);
+        for cue in &cues {
+            let lines: Vec<&str> = cue.text.lines().collect();
+            assert!(lines.len() <= options.max_lines, "{:?}", cue.text);
+            assert!(lines.iter().all(|l| l.chars().count() <= options.max_chars_per_line), "{:?}", cue.text);
+            assert!((cue.end - cue.start).as_secs() <= options.max_duration_secs);
+        }
+        let rejoined = cues.iter().map(|c| c.text.replace('\n', " ")).collect::<Vec<_>>().join(" ");
+        assert_eq!(rejoined, text.split_whitespace().collect::<Vec<_>>().join(" "));
+        for pair in cues.windows(2) {
+            assert!(pair[0].end <= pair[1].start);
+        }
+    }
+
+    #[test]
+    fn words_carried_past_a_clause_break_are_checked_again() {
+        let options = CaptionOptions {
+            max_chars_per_line: 25,
+            max_lines: 1,
+            ..CaptionOptions::default()
+        };
+        // "absolutely" is carried past "now," but doesn't fit on a line with the next word
+        let cues = segment_captions(&transcript("Alright now, absolutely incomprehensibly"), &options);
+        let texts: Vec<&str> = cues.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["Alright now,", "absolutely", "incomprehensibly"]);
+    }
+
+    #[test]
+    fn sentences_and_clauses_end_cues() {
+        let cues = segment_captions(&transcript("Hello there. How are you?"), &CaptionOptions::default());
+        let texts: Vec<&str> = cues.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["Hello there.", "How are you?"]);
+
+        let options = CaptionOptions {
+            max_chars_per_line: 20,
+            max_lines: 1,
+            ..CaptionOptions::default()
+        };
+        let cues = segment_captions(&transcript("First of all, we tried something else"), &options);
+        assert_eq!(cues[0].text, "First of all,");
+    }
+
+    #[test]
+    fn short_fast_cues_are_held_longer() {
+        let cues = segment_captions(&transcript("Hi. Absolutely unbelievable."), &CaptionOptions::default());
+        // "Hi." gets the minimum duration, clipped at the next cue
+        assert_eq!(cues[0].end, cues[1].start);
+        // 24 characters at 17 cps need about 1.4s on screen
+        assert!(((cues[1].end - cues[1].start).as_secs() - 24.0 / 17.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn wrap_balances_two_lines() {
+        assert_eq!(wrap(&["a", "bb", "ccc", "dddd", "ee"], 12), ["a bb ccc", "dddd ee"]);
+        assert_eq!(wrap(&["supercalifragilistic", "x"], 5), ["supercalifragilistic", "x"]);
+    }
+}
@@ -0,0 +1,365 @@
+use crate::error::AppError;
+use crate::timestamp::Timestamp;
+use crate::video_processor::CutPoint;
+use crate::whisper::{load_transcript, Transcript};
+use serde::{Deserialize, Serialize};
+
+/// Settings for turning speech into clips to keep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CutAnalysisOptions {
+    /// Silence between segments longer than this ends a clip
+    pub pause_threshold_secs: f64,
+    /// Clips shorter than this after padding and merging are dropped
+    pub min_clip_secs: f64,
+    /// Clips are split at segment boundaries to stay under this length
+    pub max_clip_secs: Option<f64>,
+    /// Lead-in kept before speech starts
+    ///
+    /// Left unset, transcript cuts keep everything before the first clip.
+    pub padding_before_secs: Option<f64>,
+    /// Tail kept after speech ends
+    pub padding_after_secs: f64,
+    /// Clips separated by less than this once padded are joined
+    pub merge_gap_secs: f64,
+}
+
+impl Default for CutAnalysisOptions {
+    fn default() -> Self {
+        CutAnalysisOptions {
+            pause_threshold_secs: 1.0,
+            min_clip_secs: 0.0,
+            max_clip_secs: None,
+            padding_before_secs: None,
+            padding_after_secs: 0.0,
+            merge_gap_secs: 0.0,
+        }
+    }
+}
+
+/// A stretch of media time to keep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeepRange {
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl KeepRange {
+    pub fn new(start: Timestamp, end: Timestamp) -> Self {
+        KeepRange { start, end }
+    }
+
+    pub fn duration(&self) -> f64 {
+        (self.end - self.start).as_secs()
+    }
+}
+
+/// Analyze transcript to determine strategic cut points
+pub fn analyze_transcript_for_cuts(
+    transcript_path: &str,
+    options: &CutAnalysisOptions,
+) -> Result<Vec<CutPoint>, AppError> {
+    let transcript = load_transcript(transcript_path)?;
+    Ok(cut_points_from_transcript(&transcript, options))
+}
+
+pub fn cut_points_from_transcript(transcript: &Transcript, options: &CutAnalysisOptions) -> Vec<CutPoint> {
+    let mut ranges = finish_ranges(group_speech(&speech_ranges(transcript), options), options);
+    if options.padding_before_secs.is_none() {
+        if let Some(first) = ranges.first_mut() {
+            let longest = options.max_clip_secs.map_or(first.end, Timestamp::from_secs);
+            first.start = if first.end > longest { first.end - longest } else { Timestamp::ZERO };
+        }
+    }
+    to_cut_points(&ranges)
+}
+
+/// The time covered by each transcript segment
+pub fn speech_ranges(transcript: &Transcript) -> Vec<KeepRange> {
+    transcript
+        .segments
+        .iter()
+        .filter(|segment| segment.end > segment.start)
+        .map(|segment| KeepRange::new(segment.start, segment.end))
+        .collect()
+}
+
+/// Join speech separated by short pauses into clips, splitting clips that grow too long
+pub fn group_speech(speech: &[KeepRange], options: &CutAnalysisOptions) -> Vec<KeepRange> {
+    let mut clips: Vec<KeepRange> = Vec::new();
+    for range in speech {
+        if let Some(clip) = clips.last_mut() {
+            let pause = (range.start - clip.end).as_secs();
+            let too_long = options
+                .max_clip_secs
+                .is_some_and(|max| (range.end - clip.start).as_secs() > max);
+            if pause <= options.pause_threshold_secs && !too_long {
+                if range.end > clip.end {
+                    clip.end = range.end;
+                }
+                continue;
+            }
+        }
+        clips.push(*range);
+    }
+    clips
+}
+
+/// Pad clips, merge the ones that end up close together and drop the ones that are too short
+pub fn finish_ranges(mut ranges: Vec<KeepRange>, options: &CutAnalysisOptions) -> Vec<KeepRange> {
+    ranges.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+
+    let before = Timestamp::from_secs(options.padding_before_secs.unwrap_or(0.0).max(0.0));
+    let after = Timestamp::from_secs(options.padding_after_secs.max(0.0));
+
+    let mut merged: Vec<KeepRange> = Vec::new();
+    for range in ranges {
+        let mut padded = KeepRange::new(range.start - before, range.end + after);
+        if padded.start < Timestamp::ZERO {
+            padded.start = Timestamp::ZERO;
+        }
+
+        if let Some(last) = merged.last_mut() {
+            let gap = (padded.start - last.end).as_secs();
+            let fits = !matches!(options.max_clip_secs, Some(max) if (padded.end - last.start).as_secs() > max);
+
+            if gap < 0.0 && !fits {
+                // Padding overlaps but joining would be too long, so meet in the middle
+                let middle = Timestamp::from_secs((padded.start.as_secs() + last.end.as_secs()) / 2.0);
+                last.end = middle;
+                padded.start = middle;
+            } else if gap <= options.merge_gap_secs.max(0.0) && fits {
+                if padded.end > last.end {
+                    last.end = padded.end;
+                }
+                continue;
+            }
+        }
+        merged.push(padded);
+    }
+
+    merged
+        .into_iter()
+        .filter(|range| range.duration() > 0.0 && range.duration() >= options.min_clip_secs)
+        .collect()
+}
+
+/// Time to drop for removed words running from `start` to `end`
+///
+/// The removed words always go in full. The pauses around them go too, except
+/// `padding_secs` left next to the neighbouring words so their onsets and tails
+/// aren't clipped; padding never reaches into the removed words themselves.
+pub fn removal_range(
+    start: Timestamp,
+    end: Timestamp,
+    previous_end: Option<Timestamp>,
+    next_start: Option<Timestamp>,
+    padding_secs: f64,
+) -> Option<KeepRange> {
+    let padding = Timestamp::from_secs(padding_secs.max(0.0));
+    let from = match previous_end {
+        Some(previous) if previous + padding < start => previous + padding,
+        _ => start,
+    };
+    let to = match next_start {
+        Some(next) if next - padding > end => next - padding,
+        _ => end,
+    };
+    (to > from).then(|| KeepRange::new(from, to))
+}
+
+/// Cut sorted removals out of sorted keep ranges
+pub fn subtract(ranges: &[KeepRange], removals: &[KeepRange]) -> Vec<KeepRange> {
+    let mut out = Vec::new();
+    for range in ranges {
+        let mut start = range.start;
+        for removal in removals.iter().filter(|r| r.end > range.start && r.start < range.end) {
+            if removal.start > start {
+                out.push(KeepRange::new(start, removal.start));
+            }
+            if removal.end > start {
+                start = removal.end;
+            }
+        }
+        if range.end > start {
+            out.push(KeepRange::new(start, range.end));
+        }
+    }
+    out
+}
+
+/// Lowercase a word and strip the punctuation around it, for comparing spoken words
+pub fn normalize_word(text: &str) -> String {
+    text.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()
+}
+
+pub fn to_cut_points(ranges: &[KeepRange]) -> Vec<CutPoint> {
+    ranges
+        .iter()
+        .enumerate()
+        .map(|(i, range)| CutPoint {
+            start_time: range.start.as_secs(),
+            end_time: range.end.as_secs(),
+            description: format!("Segment {}", i + 1),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use crate::whisper::{TranscriptSegment, TranscriptWord};
+
+    /// One segment per sentence, words 0.4s long with 0.1s between them
+    pub fn transcript(sentences: &[&str]) -> Transcript {
+        let mut position = 0;
+        let segments = sentences
+            .iter()
+            .map(|sentence| {
+                let words: Vec<TranscriptWord> = sentence
+                    .split_whitespace()
+                    .map(|w| {
+                        let start = position as f64 * 0.5;
+                        position += 1;
+                        TranscriptWord {
+                            text: w.to_string(),
+                            start: Timestamp::from_secs(start),
+                            end: Timestamp::from_secs(start + 0.4),
+                            probability: 1.0,
+                        }
+                    })
+                    .collect();
+                TranscriptSegment {
+                    start: words[0].start,
+                    end: words[words.len() - 1].end,
+                    text: format!(" {}", sentence),
+                    words,
+                }
+            })
+            .collect();
+        Transcript {
+            segments,
+            text: String::new(),
+            language: None,
+            language_probability: None,
+            translated: false,
+        }
+    }
+
+    /// Speech at 0.5-2.0s, 2.3-4.1s and 6.0-8.25s
+    pub fn synthetic_clip_transcript() -> Transcript {
+        let segment = |start: f64, end: f64, text: &str| TranscriptSegment {
+            start: Timestamp::from_secs(start),
+            end: Timestamp::from_secs(end),
+            text: text.to_string(),
+            words: Vec::new(),
+        };
+        Transcript {
+            segments: vec![
+                segment(0.5, 2.0, "First line."),
+                segment(2.3, 4.1, "Second line."),
+                segment(6.0, 8.25, "After a pause."),
+            ],
+            text: String::new(),
+            language: None,
+            language_probability: None,
+            translated: false,
+        }
+    }
+
+    /// Start and end seconds, rounded to the millisecond
+    pub fn secs(ranges: &[KeepRange]) -> Vec<(f64, f64)> {
+        ranges.iter().map(|r| (round(r.start.as_secs()), round(r.end.as_secs()))).collect()
+    }
+
+    /// Start and end seconds of cut points, rounded to the millisecond
+    pub fn ranges(cuts: &[CutPoint]) -> Vec<(f64, f64)> {
+        cuts.iter().map(|c| (round(c.start_time), round(c.end_time))).collect()
+    }
+
+    fn round(secs: f64) -> f64 {
+        (secs * 1000.0).round() / 1000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{ranges, synthetic_clip_transcript};
+    use super::*;
+
+    #[test]
+    fn pause_threshold_applies_to_seconds() {
+        let cuts = cut_points_from_transcript(&synthetic_clip_transcript(), &CutAnalysisOptions::default());
+        // The 0.3s gap is kept, the 1.9s pause splits the clip
+        assert_eq!(ranges(&cuts), vec![(0.0, 4.1), (6.0, 8.25)]);
+        assert_eq!(cuts[1].description, "Segment 2");
+    }
+
+    #[test]
+    fn padding_merge_and_length_limits() {
+        let transcript = synthetic_clip_transcript();
+
+        let padded = CutAnalysisOptions {
+            padding_before_secs: Some(0.75),
+            padding_after_secs: 0.25,
+            ..CutAnalysisOptions::default()
+        };
+        assert_eq!(ranges(&cut_points_from_transcript(&transcript, &padded)), vec![(0.0, 4.35), (5.25, 8.5)]);
+
+        let merged = CutAnalysisOptions {
+            merge_gap_secs: 1.0,
+            ..padded.clone()
+        };
+        assert_eq!(ranges(&cut_points_from_transcript(&transcript, &merged)), vec![(0.0, 8.5)]);
+
+        let capped = CutAnalysisOptions {
+            max_clip_secs: Some(2.0),
+            ..CutAnalysisOptions::default()
+        };
+        assert_eq!(
+            ranges(&cut_points_from_transcript(&transcript, &capped)),
+            vec![(0.0, 2.0), (2.3, 4.1), (6.0, 8.25)]
+        );
+
+        let min_length = CutAnalysisOptions {
+            min_clip_secs: 3.0,
+            ..CutAnalysisOptions::default()
+        };
+        assert_eq!(ranges(&cut_points_from_transcript(&transcript, &min_length)), vec![(0.0, 4.1)]);
+    }
+
+    #[test]
+    fn overlapping_padding_is_split_when_a_join_would_be_too_long() {
+        let speech = vec![
+            KeepRange::new(Timestamp::from_secs(0.0), Timestamp::from_secs(2.0)),
+            KeepRange::new(Timestamp::from_secs(2.2), Timestamp::from_secs(4.0)),
+        ];
+        let options = CutAnalysisOptions {
+            padding_before_secs: Some(0.2),
+            padding_after_secs: 0.2,
+            max_clip_secs: Some(3.0),
+            ..CutAnalysisOptions::default()
+        };
+        let ranges: Vec<(f64, f64)> = finish_ranges(speech, &options)
+            .iter()
+            .map(|r| ((r.start.as_secs() * 100.0).round() / 100.0, (r.end.as_secs() * 100.0).round() / 100.0))
+            .collect();
+        assert_eq!(ranges, vec![(0.0, 2.1), (2.1, 4.2)]);
+    }
+
+    #[test]
+    fn removal_padding_only_uses_real_gaps() {
+        let at = Timestamp::from_secs;
+        let secs = |range: KeepRange| ((range.start.as_secs() * 100.0).round() / 100.0, (range.end.as_secs() * 100.0).round() / 100.0);
+
+        // Whisper's word timings touch, so a short word leaves no gap to pad into
+        let touching = removal_range(at(1.0), at(1.1), Some(at(1.0)), Some(at(1.1)), 0.05).unwrap();
+        assert_eq!(secs(touching), (1.0, 1.1));
+
+        let gaps = removal_range(at(1.0), at(1.1), Some(at(0.5)), Some(at(1.5)), 0.05).unwrap();
+        assert_eq!(secs(gaps), (0.55, 1.45));
+
+        let small_gaps = removal_range(at(1.0), at(1.1), Some(at(0.98)), Some(at(1.12)), 0.05).unwrap();
+        assert_eq!(secs(small_gaps), (1.0, 1.1));
+    }
+}
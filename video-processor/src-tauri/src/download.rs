@@ -0,0 +1,332 @@
+use crate::error::AppError;
+use crate::jobs::CancelToken;
+use crate::progress::ProgressReporter;
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Download `url` to `dest`, resuming a previous partial download if there is one
+///
+/// Data is written to `<dest>.part` and only renamed into place once its SHA-256
+/// matches, which is returned. When `expected_sha256` is `None` the checksum
+/// published by the server (Hugging Face's `X-Linked-Etag`) is trusted instead,
+/// so callers only pass `None` when the user asked for that.
+pub fn download_verified(
+    url: &str,
+    dest: &Path,
+    expected_sha256: Option<&str>,
+    progress: &ProgressReporter,
+    cancel: &CancelToken,
+) -> Result<String, AppError> {
+    let expected = match expected_sha256 {
+        Some(sha) => normalize_sha256(sha)
+            .ok_or_else(|| AppError::DownloadError(format!("Invalid SHA-256 checksum: {}", sha)))?,
+        None => probe_published_sha256(url)?
+            .ok_or_else(|| AppError::DownloadError(format!("No checksum available for {}", url)))?,
+    };
+
+    let part_path = part_path(dest);
+    progress.report(0.0);
+
+    // Hash whatever is already on disk so a resumed download is verified end to end
+    let mut hasher = Sha256::new();
+    let mut downloaded = 0u64;
+    if part_path.is_file() {
+        let mut existing = File::open(&part_path)?;
+        downloaded = std::io::copy(&mut existing, &mut hasher)?;
+    }
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(30))
+        .timeout_read(Duration::from_secs(60))
+        .build();
+
+    let mut request = agent.get(url);
+    if downloaded > 0 {
+        request = request.set("Range", &format!("bytes={}-", downloaded));
+    }
+
+    let response = match request.call() {
+        Ok(response) => Some(response),
+        // The partial file already holds the whole body
+        Err(ureq::Error::Status(416, _)) if downloaded > 0 => None,
+        Err(e) => return Err(AppError::DownloadError(format!("Failed to download {}: {}", url, e))),
+    };
+
+    if let Some(response) = response {
+        let resumed = response.status() == 206;
+        if !resumed {
+            // The server ignored the range request, so start over
+            hasher = Sha256::new();
+            downloaded = 0;
+        }
+
+        let total = if resumed {
+            response
+                .header("Content-Range")
+                .and_then(|range| range.rsplit('/').next())
+                .and_then(|total| total.parse::<u64>().ok())
+        } else {
+            response.header("Content-Length").and_then(|len| len.parse::<u64>().ok())
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&part_path)?;
+
+        let mut reader = response.into_reader();
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut last_percent = None;
+        loop {
+            // Keep the partial file on cancel so the next attempt can resume
+            cancel.check()?;
+
+            let n = reader
+                .read(&mut buf)
+                .map_err(|e| AppError::DownloadError(format!("Download interrupted: {}", e)))?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n])?;
+            hasher.update(&buf[..n]);
+            downloaded += n as u64;
+
+            if let Some(total) = total.filter(|total| *total > 0) {
+                let percent = downloaded * 100 / total;
+                if last_percent != Some(percent) {
+                    last_percent = Some(percent);
+                    progress.report(downloaded as f64 / total as f64);
+                }
+            }
+        }
+
+        file.sync_all()?;
+    }
+
+    let actual = to_hex(&hasher.finalize());
+    if actual != expected {
+        // A corrupt partial file would never verify, so drop it and let the next attempt start clean
+        let _ = std::fs::remove_file(&part_path);
+        return Err(AppError::DownloadError(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            url, expected, actual
+        )));
+    }
+
+    std::fs::rename(&part_path, dest)?;
+    progress.report(1.0);
+    Ok(actual)
+}
+
+/// Path of the in-progress file for a download
+fn part_path(dest: &Path) -> PathBuf {
+    let mut path = OsString::from(dest.as_os_str());
+    path.push(".part");
+    PathBuf::from(path)
+}
+
+/// Ask the server for the SHA-256 it publishes for `url` without following redirects
+fn probe_published_sha256(url: &str) -> Result<Option<String>, AppError> {
+    let agent = ureq::AgentBuilder::new()
+        .redirects(0)
+        .timeout_connect(Duration::from_secs(30))
+        .build();
+
+    let response = agent
+        .head(url)
+        .call()
+        .map_err(|e| AppError::DownloadError(format!("Failed to reach {}: {}", url, e)))?;
+
+    Ok(response
+        .header("X-Linked-Etag")
+        .or_else(|| response.header("ETag"))
+        .and_then(normalize_sha256))
+}
+
+/// Copy `src` to `dest`, returning the SHA-256 of the copied bytes
+pub fn copy_with_sha256(src: &Path, dest: &Path) -> Result<String, AppError> {
+    let mut reader = File::open(src)?;
+    let mut file = File::create(dest)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n])?;
+        hasher.update(&buf[..n]);
+    }
+    file.sync_all()?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// SHA-256 of a file's contents
+pub fn sha256_of_file(path: &Path) -> Result<String, AppError> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Lowercase a hex SHA-256, accepting the quoted and weak forms used in ETags
+pub fn normalize_sha256(value: &str) -> Option<String> {
+    let value = value.trim().trim_start_matches("W/").trim_matches('"').to_ascii_lowercase();
+    if value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Minimal HTTP/1.1 server that serves `body` with range support and an `X-Linked-Etag`
+    pub fn serve(body: Vec<u8>, published_sha: Option<String>, connections: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ggml-test.bin", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut range_start = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        range_start = value.trim().trim_end_matches('-').parse::<usize>().ok();
+                    }
+                }
+
+                let mut head = match range_start {
+                    Some(start) if start >= body.len() => {
+                        "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Length: 0\r\n".to_string()
+                    }
+                    Some(start) => format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\n",
+                        body.len() - start,
+                        start,
+                        body.len() - 1,
+                        body.len()
+                    ),
+                    None => format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n", body.len()),
+                };
+                if let Some(sha) = &published_sha {
+                    head.push_str(&format!("X-Linked-Etag: \"{}\"\r\n", sha));
+                }
+                head.push_str("Connection: close\r\n\r\n");
+                stream.write_all(head.as_bytes()).unwrap();
+
+                if request_line.starts_with("GET") {
+                    let start = range_start.unwrap_or(0).min(body.len());
+                    if !head.starts_with("HTTP/1.1 416") {
+                        stream.write_all(&body[start..]).unwrap();
+                    }
+                }
+            }
+        });
+
+        url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::serve;
+    use super::*;
+    use crate::progress::ProgressStep;
+
+    fn test_body() -> Vec<u8> {
+        (0..300_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn quiet() -> ProgressReporter {
+        ProgressReporter::new(ProgressStep::DownloadModel, |_| {})
+    }
+
+    fn sha_of(bytes: &[u8]) -> String {
+        to_hex(&Sha256::digest(bytes))
+    }
+
+    #[test]
+    fn downloads_and_verifies_published_checksum() {
+        let body = test_body();
+        let url = serve(body.clone(), Some(sha_of(&body)), 2);
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+
+        download_verified(&url, &dest, None, &quiet(), &CancelToken::default()).unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!part_path(&dest).exists());
+    }
+
+    #[test]
+    fn resumes_partial_download() {
+        let body = test_body();
+        let url = serve(body.clone(), None, 1);
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+        std::fs::write(part_path(&dest), &body[..123_456]).unwrap();
+
+        download_verified(&url, &dest, Some(&sha_of(&body)), &quiet(), &CancelToken::default()).unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+    }
+
+    #[test]
+    fn finishes_when_partial_file_is_already_complete() {
+        let body = test_body();
+        let url = serve(body.clone(), None, 1);
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+        std::fs::write(part_path(&dest), &body).unwrap();
+
+        download_verified(&url, &dest, Some(&sha_of(&body)), &quiet(), &CancelToken::default()).unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+    }
+
+    #[test]
+    fn rejects_checksum_mismatch() {
+        let body = test_body();
+        let url = serve(body, None, 1);
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+
+        let result = download_verified(&url, &dest, Some(&"0".repeat(64)), &quiet(), &CancelToken::default());
+
+        assert!(matches!(result, Err(AppError::DownloadError(_))));
+        assert!(!dest.exists());
+        assert!(!part_path(&dest).exists());
+    }
+
+    #[test]
+    fn normalizes_etag_checksums() {
+        let sha = "AB".repeat(32);
+        assert_eq!(normalize_sha256(&format!("W/\"{}\"", sha)), Some("ab".repeat(32)));
+        assert_eq!(normalize_sha256("\"not-a-sha\""), None);
+    }
+}
@@ -12,6 +12,18 @@ pub enum AppError {
     #[error("Video processing error: {0}")]
     VideoProcessingError(String),
     
+    #[error("Download error: {0}")]
+    DownloadError(String),
+    
+    #[error("Subtitle error: {0}")]
+    SubtitleError(String),
+    
+    #[error("Transcript error: {0}")]
+    TranscriptError(String),
+    
+    #[error("Job was cancelled")]
+    Cancelled,
+    
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
     
@@ -0,0 +1,271 @@
+use crate::cuts::{
+    finish_ranges, group_speech, normalize_word, removal_range, speech_ranges, subtract, to_cut_points,
+    CutAnalysisOptions, KeepRange,
+};
+use crate::error::AppError;
+use crate::video_processor::CutPoint;
+use crate::whisper::{load_transcript, Transcript, TranscriptWord};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Longest phrase, in words, that counts as an immediate repetition
+const MAX_REPEAT_WORDS: usize = 3;
+
+/// Longest gap, not counting cut fillers, between an attempt and its repeat
+const MAX_REPEAT_GAP_SECS: f64 = 0.3;
+
+/// A pause this long after a multi-word filler sets it apart from the sentence
+const PHRASE_PAUSE_SECS: f64 = 0.25;
+
+/// What counts as a filler and how cleanly to cut it out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FillerOptions {
+    /// Filler words and phrases by language code
+    ///
+    /// Single words are cut wherever they occur. Phrases like "you know" are
+    /// only cut when followed by a comma, end punctuation or a pause, so
+    /// "do you know where" stays.
+    pub fillers: HashMap<String, Vec<String>>,
+    /// Words that are often meant twice, like "had had", by language code
+    pub repeatable: HashMap<String, Vec<String>>,
+    /// Which list to use, instead of the transcript's detected language
+    pub language: Option<String>,
+    /// Also drop restarted words, like "I I think" or "th- the"
+    pub remove_repetitions: bool,
+    /// See `removal_range`
+    pub padding_secs: f64,
+}
+
+impl Default for FillerOptions {
+    fn default() -> Self {
+        let list = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        let fillers = HashMap::from([
+            ("en".to_string(), list(&["um", "umm", "uh", "uhm", "erm", "er", "ah", "hmm", "mm", "you know", "i mean"])),
+            ("de".to_string(), list(&["äh", "ähm", "öh", "hm", "hmm"])),
+            ("fr".to_string(), list(&["euh", "heu", "bah", "hum"])),
+            ("es".to_string(), list(&["eh", "em", "ehm", "mmm"])),
+            ("nl".to_string(), list(&["eh", "ehm", "uh", "uhm"])),
+        ]);
+        let repeatable = HashMap::from([
+            ("en".to_string(), list(&["had", "that"])),
+            ("de".to_string(), list(&["die", "der", "das"])),
+            ("fr".to_string(), list(&["nous", "vous"])),
+        ]);
+        FillerOptions {
+            fillers,
+            repeatable,
+            language: None,
+            remove_repetitions: true,
+            padding_secs: 0.05,
+        }
+    }
+}
+
+/// Find cut points that keep a transcript's clips without filler words and stutters
+pub fn analyze_fillers_for_cuts(
+    transcript_path: &str,
+    fillers: &FillerOptions,
+    options: &CutAnalysisOptions,
+) -> Result<Vec<CutPoint>, AppError> {
+    let transcript = load_transcript(transcript_path)?;
+    Ok(cut_points_without_fillers(&transcript, fillers, options))
+}
+
+pub fn cut_points_without_fillers(
+    transcript: &Transcript,
+    fillers: &FillerOptions,
+    options: &CutAnalysisOptions,
+) -> Vec<CutPoint> {
+    let clips = finish_ranges(group_speech(&speech_ranges(transcript), options), options);
+    to_cut_points(&subtract(&clips, &filler_ranges(transcript, fillers)))
+}
+
+/// Time taken up by fillers and repetitions, in order
+///
+/// Segments without word timings can't be cut precisely and are left alone.
+pub fn filler_ranges(transcript: &Transcript, options: &FillerOptions) -> Vec<KeepRange> {
+    let words: Vec<&TranscriptWord> = transcript.segments.iter().flat_map(|s| &s.words).collect();
+    let normalized: Vec<String> = words.iter().map(|w| normalize_word(&w.text)).collect();
+    let mut removed = vec![false; words.len()];
+
+    let language = options
+        .language
+        .as_deref()
+        .or(transcript.language.as_deref())
+        .unwrap_or("en")
+        .to_lowercase();
+    let mut phrases: Vec<Vec<String>> = options
+        .fillers
+        .get(&language)
+        .map(|list| list.iter().map(|f| f.split_whitespace().map(normalize_word).collect()).collect())
+        .unwrap_or_default();
+    phrases.retain(|p: &Vec<String>| !p.is_empty() && p.iter().all(|w| !w.is_empty()));
+    // Try longer phrases first so "you know" isn't shadowed by a shorter entry
+    phrases.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+    let mut i = 0;
+    while i < words.len() {
+        let matches = |p: &&Vec<String>| {
+            normalized[i..].starts_with(p) && (p.len() == 1 || sets_apart(&words, i + p.len() - 1))
+        };
+        match phrases.iter().find(matches) {
+            Some(phrase) => {
+                removed[i..i + phrase.len()].fill(true);
+                i += phrase.len();
+            }
+            None => i += 1,
+        }
+    }
+
+    if options.remove_repetitions {
+        let repeatable: Vec<String> = options
+            .repeatable
+            .get(&language)
+            .map(|list| list.iter().map(|w| normalize_word(w)).collect())
+            .unwrap_or_default();
+        mark_repetitions(&words, &normalized, &repeatable, &mut removed);
+    }
+
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        if !removed[i] {
+            i += 1;
+            continue;
+        }
+        let first = i;
+        while i < words.len() && removed[i] {
+            i += 1;
+        }
+        let previous_end = first.checked_sub(1).map(|p| words[p].end);
+        let next_start = words.get(i).map(|w| w.start);
+        if let Some(range) = removal_range(words[first].start, words[i - 1].end, previous_end, next_start, options.padding_secs) {
+            ranges.push(range);
+        }
+    }
+    ranges
+}
+
+/// Whether a word ends a phrase: punctuation after it, a pause, or nothing more said
+fn sets_apart(words: &[&TranscriptWord], index: usize) -> bool {
+    let punctuated = words[index].text.trim().ends_with([',', '.', ';', ':', '?', '!', '…']);
+    punctuated || !matches!(words.get(index + 1), Some(next) if (next.start - words[index].end).as_secs() < PHRASE_PAUSE_SECS)
+}
+
+/// Time between two words, leaving out the words in between that are already cut
+fn spoken_gap(words: &[&TranscriptWord], removed: &[bool], from: usize, to: usize) -> f64 {
+    let cut: f64 = (from + 1..to).filter(|&i| removed[i]).map(|i| (words[i].end - words[i].start).as_secs()).sum();
+    (words[to].start - words[from].end).as_secs() - cut
+}
+
+/// Mark every attempt but the last of a word or short phrase said twice in a row
+///
+/// The repeat has to follow straight on, so a word picked up again after a
+/// pause, or in the next clip, is left alone.
+fn mark_repetitions(words: &[&TranscriptWord], normalized: &[String], repeatable: &[String], removed: &mut [bool]) {
+    let kept: Vec<usize> = (0..words.len()).filter(|&i| !removed[i] && !normalized[i].is_empty()).collect();
+    let close = |a: usize, b: usize, removed: &[bool]| spoken_gap(words, removed, a, b) <= MAX_REPEAT_GAP_SECS;
+
+    let mut k = 0;
+    while k < kept.len() {
+        let repeated = (1..=MAX_REPEAT_WORDS).rev().find(|&len| {
+            k + 2 * len <= kept.len()
+                && !(len == 1 && repeatable.contains(&normalized[kept[k]]))
+                && (0..len).all(|o| normalized[kept[k + o]] == normalized[kept[k + len + o]])
+                && (k..k + 2 * len - 1).all(|j| close(kept[j], kept[j + 1], removed))
+        });
+        if let Some(len) = repeated {
+            for &index in &kept[k..k + len] {
+                removed[index] = true;
+            }
+            k += len;
+            continue;
+        }
+
+        // A cut-off start of the next word, like "th-" before "the"
+        let fragment = words[kept[k]].text.trim().ends_with('-')
+            && kept
+                .get(k + 1)
+                .is_some_and(|&next| {
+                    normalized[next].starts_with(normalized[kept[k]].as_str()) && close(kept[k], next, removed)
+                });
+        if fragment {
+            removed[kept[k]] = true;
+        }
+        k += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cuts::test_support::{ranges, secs, transcript};
+    use crate::timestamp::Timestamp;
+
+    #[test]
+    fn fillers_are_cut_with_padding() {
+        let t = transcript(&["So, um, you know, it works."]);
+        // "um," and "you know," are words 1-3, between "So," ending at 0.4s and "it" starting at 2.0s
+        assert_eq!(secs(&filler_ranges(&t, &FillerOptions::default())), vec![(0.45, 1.95)]);
+
+        let cuts = cut_points_without_fillers(&t, &FillerOptions::default(), &CutAnalysisOptions::default());
+        assert_eq!(ranges(&cuts), vec![(0.0, 0.45), (1.95, 2.9)]);
+    }
+
+    #[test]
+    fn repetitions_and_stutters_keep_the_last_attempt() {
+        let t = transcript(&["I I think th- the plan is is fine"]);
+        assert_eq!(secs(&filler_ranges(&t, &FillerOptions::default())), vec![(0.0, 0.45), (1.45, 1.95), (2.95, 3.45)]);
+
+        let t = transcript(&["we tried it, we tried it again"]);
+        assert_eq!(secs(&filler_ranges(&t, &FillerOptions::default())), vec![(0.0, 1.45)]);
+
+        let no_repeats = FillerOptions {
+            remove_repetitions: false,
+            ..FillerOptions::default()
+        };
+        assert!(filler_ranges(&t, &no_repeats).is_empty());
+    }
+
+    #[test]
+    fn language_picks_the_filler_list() {
+        let mut t = transcript(&["Das ist, äh, gut"]);
+        assert!(filler_ranges(&t, &FillerOptions::default()).is_empty());
+
+        t.language = Some("de".to_string());
+        assert_eq!(secs(&filler_ranges(&t, &FillerOptions::default())), vec![(0.95, 1.45)]);
+    }
+
+    #[test]
+    fn short_fillers_between_touching_words_are_cut_in_full() {
+        let mut t = transcript(&["So uh it works"]);
+        // Whisper reports contiguous timings: "uh" runs from 0.4s to 0.5s with no gaps around it
+        for (i, (start, end)) in [(0.0, 0.4), (0.4, 0.5), (0.5, 0.8), (0.8, 1.2)].into_iter().enumerate() {
+            t.segments[0].words[i].start = Timestamp::from_secs(start);
+            t.segments[0].words[i].end = Timestamp::from_secs(end);
+        }
+        assert_eq!(secs(&filler_ranges(&t, &FillerOptions::default())), vec![(0.4, 0.5)]);
+    }
+
+    #[test]
+    fn phrases_inside_a_sentence_are_kept() {
+        let t = transcript(&["Do you know where it is? I mean, it moved."]);
+        // Only "I mean," is set apart by a comma
+        assert_eq!(secs(&filler_ranges(&t, &FillerOptions::default())), vec![(2.95, 3.95)]);
+    }
+
+    #[test]
+    fn repeats_after_a_pause_or_meant_twice_are_kept() {
+        let mut t = transcript(&["I think it's fine. Fine, next slide."]);
+        // A five second pause before "Fine,"
+        for word in &mut t.segments[0].words[4..] {
+            word.start = word.start + Timestamp::from_secs(5.0);
+            word.end = word.end + Timestamp::from_secs(5.0);
+        }
+        assert!(filler_ranges(&t, &FillerOptions::default()).is_empty());
+
+        let t = transcript(&["She had had enough and said that that was it"]);
+        assert!(filler_ranges(&t, &FillerOptions::default()).is_empty());
+    }
+}
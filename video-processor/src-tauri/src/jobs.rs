@@ -0,0 +1,209 @@
+use crate::error::AppError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Name of the Tauri event sent when a background job completes
+pub const JOB_FINISHED_EVENT: &str = "job-finished";
+
+/// How often `wait_child` checks whether the child has exited
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub type JobId = String;
+
+/// Outcome of a background job sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobFinished {
+    pub job_id: JobId,
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub cancelled: bool,
+}
+
+impl JobFinished {
+    pub fn new(job_id: JobId, outcome: Result<String, AppError>) -> Self {
+        match outcome {
+            Ok(result) => Self {
+                job_id,
+                result: Some(result),
+                error: None,
+                cancelled: false,
+            },
+            Err(e) => Self {
+                job_id,
+                result: None,
+                cancelled: matches!(e, AppError::Cancelled),
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Shared flag used to stop a running job, plus the child process it is waiting on
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<CancelInner>,
+}
+
+#[derive(Default)]
+struct CancelInner {
+    cancelled: AtomicBool,
+    child: Mutex<Option<Child>>,
+}
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Return `AppError::Cancelled` if the job has been cancelled
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            Err(AppError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Mark the job as cancelled and kill its child process, if any
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        if let Some(child) = self.lock_child().as_mut() {
+            // The child may already have exited, which is fine
+            let _ = child.kill();
+        }
+    }
+
+    /// Take ownership of a spawned child so `cancel` can kill it
+    pub fn attach_child(&self, mut child: Child) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(AppError::Cancelled);
+        }
+        *self.lock_child() = Some(child);
+        Ok(())
+    }
+
+    /// Wait for the attached child to exit and release it
+    ///
+    /// Polls instead of blocking in `wait`, so the lock is free for `cancel`
+    /// to kill the child in the meantime.
+    pub fn wait_child(&self) -> Result<ExitStatus, AppError> {
+        loop {
+            let mut guard = self.lock_child();
+            let child = guard
+                .as_mut()
+                .ok_or_else(|| AppError::Other("No child process attached to job".to_string()))?;
+            if let Some(status) = child.try_wait()? {
+                *guard = None;
+                drop(guard);
+
+                self.check()?;
+                return Ok(status);
+            }
+            drop(guard);
+            std::thread::sleep(CHILD_POLL_INTERVAL);
+        }
+    }
+
+    fn lock_child(&self) -> std::sync::MutexGuard<'_, Option<Child>> {
+        self.inner.child.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Tracks running jobs so they can be cancelled by id
+#[derive(Default)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<JobId, CancelToken>>,
+}
+
+impl JobRegistry {
+    /// Register a new job and return its id and cancel token
+    pub fn start(&self) -> (JobId, CancelToken) {
+        let id = format!("job-{}", self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+        let token = CancelToken::default();
+        self.lock_jobs().insert(id.clone(), token.clone());
+        (id, token)
+    }
+
+    /// Cancel a running job
+    pub fn cancel(&self, job_id: &str) -> Result<(), AppError> {
+        let token = self
+            .lock_jobs()
+            .get(job_id)
+            .cloned()
+            .ok_or_else(|| AppError::Other(format!("No running job with id {}", job_id)))?;
+        token.cancel();
+        Ok(())
+    }
+
+    /// Forget a job once it has completed
+    pub fn finish(&self, job_id: &str) {
+        self.lock_jobs().remove(job_id);
+    }
+
+    fn lock_jobs(&self) -> std::sync::MutexGuard<'_, HashMap<JobId, CancelToken>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use std::time::Instant;
+
+    #[cfg(unix)]
+    fn long_running_child() -> Child {
+        Command::new("sleep").arg("30").spawn().unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelling_before_attach_kills_the_child() {
+        let token = CancelToken::default();
+        token.cancel();
+        assert!(matches!(token.attach_child(long_running_child()), Err(AppError::Cancelled)));
+        assert!(token.lock_child().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelling_kills_the_child_being_waited_on() {
+        let token = CancelToken::default();
+        token.attach_child(long_running_child()).unwrap();
+
+        let waiter = {
+            let token = token.clone();
+            std::thread::spawn(move || token.wait_child())
+        };
+        std::thread::sleep(Duration::from_millis(200));
+        let started = Instant::now();
+        token.cancel();
+
+        assert!(matches!(waiter.join().unwrap(), Err(AppError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(token.lock_child().is_none());
+    }
+
+    #[test]
+    fn finished_jobs_leave_the_registry() {
+        let registry = JobRegistry::default();
+        let (first, token) = registry.start();
+        let (second, _) = registry.start();
+        assert_ne!(first, second);
+
+        registry.cancel(&first).unwrap();
+        assert!(token.is_cancelled());
+
+        registry.finish(&first);
+        assert!(registry.cancel(&first).is_err());
+        assert!(registry.cancel(&second).is_ok());
+    }
+}
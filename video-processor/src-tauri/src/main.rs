@@ -4,28 +4,270 @@
 mod video_processor;
 mod whisper;
 mod error;
+mod progress;
+mod jobs;
+mod download;
+mod timestamp;
+mod audio;
+mod model_cache;
+mod vocabulary;
+mod subtitles;
+mod captions;
+mod transcript_editor;
+mod cuts;
+mod silence;
+mod fillers;
+mod retakes;
+mod text_cuts;
 
-use video_processor::{extract_audio, process_video, VideoProcessingOptions};
-use whisper::transcribe_audio;
+use video_processor::{extract_audio, process_video, CutPoint, VideoProcessingOptions};
+use whisper::{transcribe_audio, ModelSource, TranscriptionOptions, WhisperModelInfo, DEFAULT_MODEL};
 use error::AppError;
+use model_cache::ModelCache;
+use transcript_editor::{TranscriptEdit, TranscriptEditor, TranscriptVersion};
+use whisper::Transcript;
+use jobs::{CancelToken, JobFinished, JobId, JobRegistry, JOB_FINISHED_EVENT};
+use progress::{ProgressReporter, ProgressStep, PROGRESS_EVENT};
+use tauri::{AppHandle, Emitter, Manager, State};
 
+/// Build a reporter that emits progress for `step` of a job as Tauri events
+fn progress_reporter(app: &AppHandle, step: ProgressStep, job_id: &str) -> ProgressReporter {
+    let app = app.clone();
+    ProgressReporter::new(step, move |event| {
+        if let Err(e) = app.emit(PROGRESS_EVENT, event) {
+            log::warn!("Failed to emit progress event: {}", e);
+        }
+    })
+    .with_job_id(job_id)
+}
+
+/// Run `work` on a blocking thread as a cancellable job and return its id straight away
+///
+/// The outcome is delivered to the frontend as a `job-finished` event.
+fn spawn_job<F>(app: &AppHandle, step: ProgressStep, work: F) -> JobId
+where
+    F: FnOnce(&ProgressReporter, &CancelToken) -> Result<String, AppError> + Send + 'static,
+{
+    let (job_id, cancel) = app.state::<JobRegistry>().start();
+    let progress = progress_reporter(app, step, &job_id);
+    let app = app.clone();
+    let id = job_id.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let outcome = work(&progress, &cancel);
+        app.state::<JobRegistry>().finish(&id);
+        if let Err(e) = app.emit(JOB_FINISHED_EVENT, JobFinished::new(id, outcome)) {
+            log::warn!("Failed to emit job-finished event: {}", e);
+        }
+    });
+
+    job_id
+}
+
+#[tauri::command]
+async fn extract_audio_from_video(app: AppHandle, video_path: String) -> Result<JobId, AppError> {
+    Ok(spawn_job(&app, ProgressStep::ExtractAudio, move |progress, cancel| {
+        extract_audio(&video_path, progress, cancel)
+    }))
+}
+
+#[tauri::command]
+async fn transcribe_audio_file(
+    app: AppHandle,
+    audio_path: String,
+    model: Option<String>,
+    options: Option<TranscriptionOptions>,
+) -> Result<JobId, AppError> {
+    let model = model.unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    let options = options.unwrap_or_default();
+    let handle = app.clone();
+    Ok(spawn_job(&app, ProgressStep::Transcribe, move |progress, cancel| {
+        let models = handle.state::<ModelCache>();
+        transcribe_audio(&audio_path, &model, &models, &options, progress, cancel)
+    }))
+}
+
+/// Free a loaded Whisper model, or all of them when `model` is omitted
+#[tauri::command]
+async fn unload_whisper_model(
+    models: State<'_, ModelCache>,
+    model: Option<String>,
+) -> Result<Vec<String>, AppError> {
+    Ok(models.unload(model.as_deref()))
+}
+
+/// Re-run a project's vocabulary rules over an existing transcript file
 #[tauri::command]
-async fn extract_audio_from_video(video_path: String) -> Result<String, AppError> {
-    extract_audio(&video_path).map_err(AppError::from)
+async fn apply_vocabulary(transcript_path: String, vocabulary: vocabulary::Vocabulary) -> Result<(), AppError> {
+    vocabulary::apply_vocabulary_to_file(&transcript_path, &vocabulary)
+}
+
+/// Write a transcript as subtitles or plain text, returning the output path
+#[tauri::command]
+async fn export_transcript(
+    transcript_path: String,
+    format: subtitles::SubtitleFormat,
+    output_path: Option<String>,
+    captions: Option<captions::CaptionOptions>,
+) -> Result<String, AppError> {
+    subtitles::export_transcript(&transcript_path, format, output_path.as_deref(), captions.as_ref())
 }
 
+/// Turn existing SRT, WebVTT or ASS captions into a transcript, returning its path
 #[tauri::command]
-async fn transcribe_audio_file(audio_path: String) -> Result<String, AppError> {
-    transcribe_audio(&audio_path).map_err(AppError::from)
+async fn import_subtitles(subtitle_path: String, output_path: Option<String>) -> Result<String, AppError> {
+    subtitles::import_subtitles(&subtitle_path, output_path.as_deref())
+}
+
+/// Find the clips worth keeping from the pauses in a transcript
+#[tauri::command]
+async fn analyze_transcript_for_cuts(
+    transcript_path: String,
+    options: Option<cuts::CutAnalysisOptions>,
+) -> Result<Vec<CutPoint>, AppError> {
+    cuts::analyze_transcript_for_cuts(&transcript_path, &options.unwrap_or_default())
+}
+
+/// Find the clips worth keeping from the audio level, optionally limited to transcript clips
+#[tauri::command]
+async fn analyze_audio_for_cuts(
+    audio_path: String,
+    transcript_path: Option<String>,
+    silence: Option<silence::SilenceOptions>,
+    options: Option<cuts::CutAnalysisOptions>,
+) -> Result<Vec<CutPoint>, AppError> {
+    silence::analyze_audio_for_cuts(
+        &audio_path,
+        transcript_path.as_deref(),
+        &silence.unwrap_or_default(),
+        &options.unwrap_or_default(),
+    )
+}
+
+/// Find the clips worth keeping with filler words and stutters cut out
+#[tauri::command]
+async fn analyze_fillers_for_cuts(
+    transcript_path: String,
+    fillers: Option<fillers::FillerOptions>,
+    options: Option<cuts::CutAnalysisOptions>,
+) -> Result<Vec<CutPoint>, AppError> {
+    fillers::analyze_fillers_for_cuts(&transcript_path, &fillers.unwrap_or_default(), &options.unwrap_or_default())
+}
+
+/// Find the clips worth keeping with only the last take of repeated sentences
+#[tauri::command]
+async fn analyze_retakes_for_cuts(
+    transcript_path: String,
+    retakes: Option<retakes::RetakeOptions>,
+    options: Option<cuts::CutAnalysisOptions>,
+) -> Result<retakes::RetakeAnalysis, AppError> {
+    retakes::analyze_retakes_for_cuts(&transcript_path, &retakes.unwrap_or_default(), &options.unwrap_or_default())
+}
+
+/// Turn words and segments deleted from a transcript into cut points for processing
+#[tauri::command]
+async fn cut_points_from_text(
+    transcript_path: String,
+    deletions: Vec<text_cuts::Deletion>,
+    options: Option<text_cuts::TextCutOptions>,
+) -> Result<Vec<CutPoint>, AppError> {
+    text_cuts::cut_points_from_text_file(&transcript_path, &deletions, &options.unwrap_or_default())
+}
+
+/// Load a transcript for editing, along with the history saved next to it
+#[tauri::command]
+async fn open_transcript(editor: State<'_, TranscriptEditor>, transcript_path: String) -> Result<Transcript, AppError> {
+    editor.open(&transcript_path)
+}
+
+#[tauri::command]
+async fn edit_transcript(
+    editor: State<'_, TranscriptEditor>,
+    transcript_path: String,
+    edit: TranscriptEdit,
+) -> Result<Transcript, AppError> {
+    editor.update(&transcript_path, |history| history.apply(&edit))
+}
+
+#[tauri::command]
+async fn undo_transcript_edit(editor: State<'_, TranscriptEditor>, transcript_path: String) -> Result<Transcript, AppError> {
+    editor.update(&transcript_path, |history| history.undo())
+}
+
+#[tauri::command]
+async fn redo_transcript_edit(editor: State<'_, TranscriptEditor>, transcript_path: String) -> Result<Transcript, AppError> {
+    editor.update(&transcript_path, |history| history.redo())
+}
+
+#[tauri::command]
+async fn list_transcript_versions(
+    editor: State<'_, TranscriptEditor>,
+    transcript_path: String,
+) -> Result<Vec<TranscriptVersion>, AppError> {
+    editor.versions(&transcript_path)
+}
+
+#[tauri::command]
+async fn restore_transcript_version(
+    editor: State<'_, TranscriptEditor>,
+    transcript_path: String,
+    version: u64,
+) -> Result<Transcript, AppError> {
+    editor.update(&transcript_path, |history| history.restore(version))
+}
+
+#[tauri::command]
+async fn list_whisper_models() -> Result<Vec<WhisperModelInfo>, AppError> {
+    whisper::list_models()
+}
+
+#[tauri::command]
+async fn download_whisper_model(
+    app: AppHandle,
+    model: String,
+    source: Option<ModelSource>,
+) -> Result<JobId, AppError> {
+    let source = source.unwrap_or_default();
+    Ok(spawn_job(&app, ProgressStep::DownloadModel, move |progress, cancel| {
+        whisper::download_whisper_model(&model, &source, progress, cancel)
+    }))
+}
+
+#[tauri::command]
+async fn import_whisper_model(
+    models: State<'_, ModelCache>,
+    path: String,
+    model: Option<String>,
+    sha256: Option<String>,
+) -> Result<String, AppError> {
+    let model = whisper::import_whisper_model(&path, model.as_deref(), sha256.as_deref())?;
+    // A replaced model file must not keep serving the old weights
+    models.unload(Some(&model));
+    Ok(model)
+}
+
+#[tauri::command]
+async fn delete_whisper_model(models: State<'_, ModelCache>, model: String) -> Result<(), AppError> {
+    whisper::delete_whisper_model(&model)?;
+    models.unload(Some(&model));
+    Ok(())
 }
 
 #[tauri::command]
 async fn process_video_file(
+    app: AppHandle,
     video_path: String,
     transcript_path: String,
     options: VideoProcessingOptions,
-) -> Result<String, AppError> {
-    process_video(&video_path, &transcript_path, options).map_err(AppError::from)
+) -> Result<JobId, AppError> {
+    Ok(spawn_job(&app, ProgressStep::Render, move |progress, cancel| {
+        process_video(&video_path, &transcript_path, options, progress, cancel)
+    }))
+}
+
+#[tauri::command]
+async fn cancel_job(jobs: State<'_, JobRegistry>, job_id: String) -> Result<(), AppError> {
+    jobs.cancel(&job_id)
 }
 
 fn main() {
@@ -35,10 +277,33 @@ fn main() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
+        .manage(JobRegistry::default())
+        .manage(ModelCache::default())
+        .manage(TranscriptEditor::default())
         .invoke_handler(tauri::generate_handler![
             extract_audio_from_video,
             transcribe_audio_file,
-            process_video_file
+            process_video_file,
+            cancel_job,
+            list_whisper_models,
+            download_whisper_model,
+            import_whisper_model,
+            delete_whisper_model,
+            unload_whisper_model,
+            apply_vocabulary,
+            export_transcript,
+            import_subtitles,
+            analyze_transcript_for_cuts,
+            analyze_audio_for_cuts,
+            analyze_fillers_for_cuts,
+            analyze_retakes_for_cuts,
+            cut_points_from_text,
+            open_transcript,
+            edit_transcript,
+            undo_transcript_edit,
+            redo_transcript_edit,
+            list_transcript_versions,
+            restore_transcript_version
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
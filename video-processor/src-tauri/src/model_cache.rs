@@ -0,0 +1,219 @@
+use crate::error::AppError;
+use crate::jobs::CancelToken;
+use crate::progress::ProgressReporter;
+use crate::whisper::resolve_model;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+use whisper_rs::{WhisperContext, WhisperContextParameters, WhisperState};
+
+/// Idle states kept around per model; each one holds its own KV cache in memory
+const MAX_IDLE_STATES: usize = 2;
+
+/// Whisper models loaded into memory, keyed by model id
+///
+/// Held in managed Tauri state so consecutive transcriptions skip reloading the model.
+pub struct ModelCache<M = LoadedModel> {
+    models: Mutex<HashMap<String, Arc<M>>>,
+}
+
+impl Default for ModelCache {
+    fn default() -> Self {
+        ModelCache {
+            models: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl ModelCache {
+    /// Return the loaded model, downloading and loading it first if needed
+    pub fn load(
+        &self,
+        model_id: &str,
+        progress: &ProgressReporter,
+        cancel: &CancelToken,
+    ) -> Result<Arc<LoadedModel>, AppError> {
+        self.get_or_load(model_id, || {
+            let model_path = resolve_model(model_id, progress, cancel)?;
+            eprintln!("Loading Whisper model {}", model_id);
+            let ctx = WhisperContext::new_with_params(&model_path, WhisperContextParameters::default())
+                .map_err(|e| AppError::WhisperError(format!("Failed to load Whisper model: {}", e)))?;
+            Ok(LoadedModel {
+                ctx,
+                states: StatePool::default(),
+            })
+        })
+    }
+}
+
+impl<M> ModelCache<M> {
+    /// Return the cached model, or run `load` and cache what it returns
+    fn get_or_load(&self, model_id: &str, load: impl FnOnce() -> Result<M, AppError>) -> Result<Arc<M>, AppError> {
+        if let Some(model) = self.lock_models().get(model_id) {
+            eprintln!("Reusing loaded Whisper model {}", model_id);
+            return Ok(model.clone());
+        }
+
+        // Load without holding the lock so other models stay usable meanwhile
+        let loaded = Arc::new(load()?);
+
+        // If another job loaded the same model in the meantime, share that one
+        let mut models = self.lock_models();
+        Ok(models.entry(model_id.to_string()).or_insert(loaded).clone())
+    }
+
+    /// Drop a model from memory, or every model when `model_id` is `None`
+    ///
+    /// Transcriptions already using the model keep it alive until they finish.
+    /// Returns the ids that were unloaded.
+    pub fn unload(&self, model_id: Option<&str>) -> Vec<String> {
+        let mut models = self.lock_models();
+        match model_id {
+            Some(id) => models.remove_entry(id).map(|(id, _)| id).into_iter().collect(),
+            None => models.drain().map(|(id, _)| id).collect(),
+        }
+    }
+
+    fn lock_models(&self) -> MutexGuard<'_, HashMap<String, Arc<M>>> {
+        self.models.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A loaded Whisper context with a pool of states for running transcriptions on it
+pub struct LoadedModel {
+    ctx: WhisperContext,
+    states: StatePool<WhisperState>,
+}
+
+impl LoadedModel {
+    pub fn context(&self) -> &WhisperContext {
+        &self.ctx
+    }
+
+    /// Take an idle state from the pool, or create one so concurrent jobs don't wait
+    pub fn checkout_state(&self) -> Result<PooledState<'_>, AppError> {
+        self.states.checkout(|| {
+            self.ctx
+                .create_state()
+                .map_err(|e| AppError::WhisperError(format!("Failed to create Whisper state: {}", e)))
+        })
+    }
+}
+
+/// Idle states ready for reuse, at most `MAX_IDLE_STATES` of them
+struct StatePool<S> {
+    idle: Mutex<Vec<S>>,
+}
+
+impl<S> Default for StatePool<S> {
+    fn default() -> Self {
+        StatePool {
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<S> StatePool<S> {
+    fn checkout(&self, create: impl FnOnce() -> Result<S, AppError>) -> Result<Pooled<'_, S>, AppError> {
+        let idle = self.lock_idle().pop();
+        let state = match idle {
+            Some(state) => state,
+            None => create()?,
+        };
+        Ok(Pooled {
+            pool: self,
+            state: Some(state),
+        })
+    }
+
+    fn lock_idle(&self) -> MutexGuard<'_, Vec<S>> {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A `WhisperState` borrowed from a `LoadedModel`, returned to its pool on drop
+pub type PooledState<'a> = Pooled<'a, WhisperState>;
+
+/// A state borrowed from a `StatePool`, returned to it on drop
+pub struct Pooled<'a, S> {
+    pool: &'a StatePool<S>,
+    state: Option<S>,
+}
+
+impl<S> Deref for Pooled<'_, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.state.as_ref().expect("state is only taken on drop")
+    }
+}
+
+impl<S> DerefMut for Pooled<'_, S> {
+    fn deref_mut(&mut self) -> &mut S {
+        self.state.as_mut().expect("state is only taken on drop")
+    }
+}
+
+impl<S> Drop for Pooled<'_, S> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            let mut idle = self.pool.lock_idle();
+            if idle.len() < MAX_IDLE_STATES {
+                idle.push(state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> ModelCache<&'static str> {
+        ModelCache {
+            models: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[test]
+    fn loaded_models_are_reused_until_unloaded() {
+        let cache = cache();
+        let first = cache.get_or_load("base", || Ok("base")).unwrap();
+        let again = cache.get_or_load("base", || panic!("base should already be loaded")).unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+
+        assert!(cache.get_or_load("tiny", || Err(AppError::Other("no model".to_string()))).is_err());
+        cache.get_or_load("tiny", || Ok("tiny")).unwrap();
+
+        assert_eq!(cache.unload(Some("base")), ["base"]);
+        assert!(cache.unload(Some("base")).is_empty());
+        // A job still holding the unloaded model keeps it alive
+        assert_eq!(*first, "base");
+        let reloaded = cache.get_or_load("base", || Ok("base")).unwrap();
+        assert!(!Arc::ptr_eq(&first, &reloaded));
+
+        let mut unloaded = cache.unload(None);
+        unloaded.sort();
+        assert_eq!(unloaded, ["base", "tiny"]);
+    }
+
+    #[test]
+    fn pool_reuses_states_and_keeps_only_a_few_idle() {
+        let pool = StatePool::default();
+        let created = std::cell::Cell::new(0);
+        let create = || {
+            created.set(created.get() + 1);
+            Ok(created.get())
+        };
+
+        let first = pool.checkout(create).unwrap();
+        assert_eq!(*first, 1);
+        drop(first);
+        assert_eq!(*pool.checkout(create).unwrap(), 1);
+
+        let busy: Vec<_> = (0..MAX_IDLE_STATES + 2).map(|_| pool.checkout(create).unwrap()).collect();
+        assert_eq!(created.get(), MAX_IDLE_STATES + 2);
+        drop(busy);
+        assert_eq!(pool.lock_idle().len(), MAX_IDLE_STATES);
+    }
+}
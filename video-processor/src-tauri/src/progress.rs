@@ -0,0 +1,133 @@
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Name of the Tauri event that carries `ProgressEvent` payloads
+pub const PROGRESS_EVENT: &str = "progress";
+
+/// The long-running step a progress event belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProgressStep {
+    ExtractAudio,
+    Transcribe,
+    Render,
+    DownloadModel,
+}
+
+/// Progress update sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEvent {
+    pub job_id: Option<String>,
+    pub step: ProgressStep,
+    pub percent: f64,
+    pub elapsed_secs: f64,
+    pub eta_secs: Option<f64>,
+}
+
+/// Turns raw completion fractions into `ProgressEvent`s with elapsed time and ETA
+#[derive(Clone)]
+pub struct ProgressReporter {
+    job_id: Option<String>,
+    step: ProgressStep,
+    started: Instant,
+    sink: Arc<dyn Fn(ProgressEvent) + Send + Sync>,
+}
+
+impl ProgressReporter {
+    pub fn new(step: ProgressStep, sink: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            job_id: None,
+            step,
+            started: Instant::now(),
+            sink: Arc::new(sink),
+        }
+    }
+
+    /// Tag every event with the id of the job it belongs to
+    pub fn with_job_id(mut self, job_id: impl Into<String>) -> Self {
+        self.job_id = Some(job_id.into());
+        self
+    }
+
+    /// Reporter for a different step of the same job, with its own clock
+    pub fn for_step(&self, step: ProgressStep) -> Self {
+        Self {
+            job_id: self.job_id.clone(),
+            step,
+            started: Instant::now(),
+            sink: self.sink.clone(),
+        }
+    }
+
+    /// Report completion as a fraction between 0 and 1
+    pub fn report(&self, fraction: f64) {
+        let fraction = if fraction.is_finite() { fraction.clamp(0.0, 1.0) } else { 0.0 };
+        let elapsed_secs = self.started.elapsed().as_secs_f64();
+        let eta_secs = if fraction > 0.0 {
+            Some(elapsed_secs * (1.0 - fraction) / fraction)
+        } else {
+            None
+        };
+
+        (self.sink)(ProgressEvent {
+            job_id: self.job_id.clone(),
+            step: self.step,
+            percent: fraction * 100.0,
+            elapsed_secs,
+            eta_secs,
+        });
+    }
+}
+
+/// Parse the output position in seconds from a line of FFmpeg's `-progress` output
+pub fn parse_ffmpeg_out_time(line: &str) -> Option<f64> {
+    let (key, value) = line.trim().split_once('=')?;
+    // `out_time_ms` is misnamed by FFmpeg and is also in microseconds
+    if key != "out_time_us" && key != "out_time_ms" {
+        return None;
+    }
+    let micros = value.parse::<i64>().ok()?;
+    if micros < 0 {
+        return None;
+    }
+    Some(micros as f64 / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn parses_ffmpeg_progress_lines() {
+        assert_eq!(parse_ffmpeg_out_time("out_time_us=2500000"), Some(2.5));
+        assert_eq!(parse_ffmpeg_out_time("out_time_ms=1000000\n"), Some(1.0));
+        assert_eq!(parse_ffmpeg_out_time("out_time_us=N/A"), None);
+        assert_eq!(parse_ffmpeg_out_time("out_time_us=-9223372036854775807"), None);
+        assert_eq!(parse_ffmpeg_out_time("out_time=00:00:02.500000"), None);
+        assert_eq!(parse_ffmpeg_out_time("progress=continue"), None);
+    }
+
+    #[test]
+    fn reports_percent_and_eta() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let reporter = ProgressReporter::new(ProgressStep::Render, move |event| {
+            sink.lock().unwrap().push(event)
+        });
+
+        reporter.report(0.0);
+        reporter.report(0.5);
+        reporter.report(7.0);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0].percent, 0.0);
+        assert!(events[0].eta_secs.is_none());
+        assert_eq!(events[1].percent, 50.0);
+        assert!(events[1].eta_secs.is_some());
+        assert_eq!(events[2].percent, 100.0);
+        assert_eq!(events[2].eta_secs, Some(0.0));
+    }
+}
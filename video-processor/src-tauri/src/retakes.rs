@@ -0,0 +1,267 @@
+use crate::cuts::{
+    finish_ranges, group_speech, normalize_word, removal_range, speech_ranges, subtract, to_cut_points,
+    CutAnalysisOptions,
+};
+use crate::error::AppError;
+use crate::timestamp::Timestamp;
+use crate::video_processor::CutPoint;
+use crate::whisper::{load_transcript, Transcript};
+use serde::{Deserialize, Serialize};
+
+/// A pause this long between words ends a sentence even without punctuation
+const PAUSE_BREAK_SECS: f64 = 1.0;
+
+/// Settings for spotting repeated attempts at the same sentence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RetakeOptions {
+    /// How alike two sentences must be, from 0 to 1, to count as takes of the same line
+    pub similarity_threshold: f64,
+    /// Shorter sentences are never treated as takes
+    pub min_words: usize,
+    /// See `removal_range`
+    pub padding_secs: f64,
+    /// Sentence indices an editor chose to keep even though they look like retakes
+    pub keep_sentences: Vec<usize>,
+}
+
+impl Default for RetakeOptions {
+    fn default() -> Self {
+        RetakeOptions {
+            similarity_threshold: 0.8,
+            min_words: 3,
+            padding_secs: 0.05,
+            keep_sentences: Vec::new(),
+        }
+    }
+}
+
+/// An earlier attempt that a later sentence replaces
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedTake {
+    /// Index of the sentence, usable in `keep_sentences`
+    pub sentence: usize,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub text: String,
+    /// How close it is to the next sentence, from 0 to 1
+    pub similarity: f64,
+}
+
+/// Cut points for the takes to keep, and the takes they leave out
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetakeAnalysis {
+    pub cut_points: Vec<CutPoint>,
+    pub rejected_takes: Vec<RejectedTake>,
+}
+
+/// A sentence with its timing and the words used for comparison
+#[derive(Debug, Clone)]
+struct Sentence {
+    start: Timestamp,
+    end: Timestamp,
+    text: String,
+    words: Vec<String>,
+}
+
+/// Find cut points that keep only the last take of repeated sentences
+pub fn analyze_retakes_for_cuts(
+    transcript_path: &str,
+    retakes: &RetakeOptions,
+    options: &CutAnalysisOptions,
+) -> Result<RetakeAnalysis, AppError> {
+    let transcript = load_transcript(transcript_path)?;
+    Ok(find_retakes(&transcript, retakes, options))
+}
+
+pub fn find_retakes(transcript: &Transcript, retakes: &RetakeOptions, options: &CutAnalysisOptions) -> RetakeAnalysis {
+    let sentences = sentences(transcript);
+
+    let mut rejected_takes = Vec::new();
+    let mut removals = Vec::new();
+    for (i, pair) in sentences.windows(2).enumerate() {
+        let (take, next) = (&pair[0], &pair[1]);
+        if take.words.len() < retakes.min_words.max(1) || retakes.keep_sentences.contains(&i) {
+            continue;
+        }
+        let similarity = similarity(&take.words, &next.words, is_abandoned(&take.text));
+        if similarity < retakes.similarity_threshold {
+            continue;
+        }
+
+        let previous_end = i.checked_sub(1).map(|p| sentences[p].end);
+        if let Some(range) = removal_range(take.start, take.end, previous_end, Some(next.start), retakes.padding_secs) {
+            removals.push(range);
+        }
+        rejected_takes.push(RejectedTake {
+            sentence: i,
+            start: take.start,
+            end: take.end,
+            text: take.text.clone(),
+            similarity,
+        });
+    }
+
+    let clips = finish_ranges(group_speech(&speech_ranges(transcript), options), options);
+    RetakeAnalysis {
+        cut_points: to_cut_points(&subtract(&clips, &removals)),
+        rejected_takes,
+    }
+}
+
+/// Split a transcript into sentences at end punctuation and long pauses
+///
+/// Segments without word timings are taken as one sentence each.
+fn sentences(transcript: &Transcript) -> Vec<Sentence> {
+    let mut sentences = Vec::new();
+    let mut current: Option<Sentence> = None;
+
+    for segment in &transcript.segments {
+        if segment.words.is_empty() {
+            sentences.extend(current.take());
+            sentences.push(Sentence {
+                start: segment.start,
+                end: segment.end,
+                text: segment.text.trim().to_string(),
+                words: segment.text.split_whitespace().map(normalize_word).filter(|w| !w.is_empty()).collect(),
+            });
+            continue;
+        }
+
+        for word in &segment.words {
+            let text = word.text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            if current.as_ref().is_some_and(|s| (word.start - s.end).as_secs() >= PAUSE_BREAK_SECS) {
+                sentences.extend(current.take());
+            }
+
+            let sentence = current.get_or_insert_with(|| Sentence {
+                start: word.start,
+                end: word.end,
+                text: String::new(),
+                words: Vec::new(),
+            });
+            if !sentence.text.is_empty() {
+                sentence.text.push(' ');
+            }
+            sentence.text.push_str(text);
+            sentence.end = word.end;
+            let normalized = normalize_word(text);
+            if !normalized.is_empty() {
+                sentence.words.push(normalized);
+            }
+
+            let ends_sentence = text
+                .trim_end_matches(['"', '\'', '”', '’', ')', ']'])
+                .ends_with(['.', '?', '!', '…']);
+            if ends_sentence {
+                sentences.extend(current.take());
+            }
+        }
+    }
+    sentences.extend(current.take());
+    sentences
+}
+
+/// Whether a sentence trails off instead of being finished
+fn is_abandoned(text: &str) -> bool {
+    let text = text.trim_end_matches(['"', '\'', '”', '’', ')', ']']);
+    text.ends_with("...") || text.ends_with(['…', '-', '–', '—']) || !text.ends_with(['.', '?', '!'])
+}
+
+/// Character-level likeness of an attempt to the sentence after it, from 0 to 1
+///
+/// An abandoned attempt is compared with the same number of words from the start
+/// of the next sentence; finished sentences are compared whole.
+fn similarity(take: &[String], next: &[String], abandoned: bool) -> f64 {
+    let next = if abandoned { &next[..next.len().min(take.len())] } else { next };
+    let a: Vec<char> = take.join(" ").chars().collect();
+    let b: Vec<char> = next.join(" ").chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / longest as f64
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cuts::test_support::{ranges, transcript};
+
+    fn rejected(analysis: &RetakeAnalysis) -> Vec<&str> {
+        analysis.rejected_takes.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn earlier_takes_are_cut_and_listed() {
+        let t = transcript(&[
+            "Welcome back.",
+            "The new build is way faster.",
+            "The new build is much faster.",
+            "We tested it on Linux.",
+            "We tested it on Windows.",
+        ]);
+        let analysis = find_retakes(&t, &RetakeOptions::default(), &CutAnalysisOptions::default());
+
+        assert_eq!(rejected(&analysis), ["The new build is way faster."]);
+        assert_eq!(analysis.rejected_takes[0].sentence, 1);
+        // "Welcome back." ends at 0.9s and the last take starts at 4.0s
+        assert_eq!(ranges(&analysis.cut_points), vec![(0.0, 0.95), (3.95, 11.9)]);
+    }
+
+    #[test]
+    fn abandoned_attempts_and_overrides() {
+        let t = transcript(&["So today we… So today we will… So today we will look at caching."]);
+        let analysis = find_retakes(&t, &RetakeOptions::default(), &CutAnalysisOptions::default());
+        assert_eq!(rejected(&analysis), ["So today we…", "So today we will…"]);
+
+        let options = RetakeOptions {
+            keep_sentences: vec![0],
+            ..RetakeOptions::default()
+        };
+        let analysis = find_retakes(&t, &options, &CutAnalysisOptions::default());
+        assert_eq!(rejected(&analysis), ["So today we will…"]);
+    }
+
+    #[test]
+    fn similarity_is_fuzzy() {
+        let words = |s: &str| s.split_whitespace().map(normalize_word).collect::<Vec<_>>();
+        assert_eq!(
+            similarity(&words("the render pipline"), &words("The render pipeline is done."), true),
+            1.0 - 1.0 / 19.0
+        );
+        assert!(similarity(&words("we tested it on linux"), &words("we tested it on windows"), false) < 0.8);
+
+        assert!(is_abandoned("So today we…"));
+        assert!(is_abandoned("we tried the"));
+        assert!(is_abandoned("and then we-"));
+        assert!(!is_abandoned("It works.\""));
+    }
+
+    #[test]
+    fn finished_sentences_that_only_start_alike_are_kept() {
+        let t = transcript(&["Let's look at caching. Let's look at the render queue and the scheduler."]);
+        let analysis = find_retakes(&t, &RetakeOptions::default(), &CutAnalysisOptions::default());
+        assert!(analysis.rejected_takes.is_empty());
+        assert_eq!(analysis.cut_points.len(), 1);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::audio::{AudioReader, ChannelSelection, WHISPER_SAMPLE_RATE};
+use crate::cuts::{finish_ranges, group_speech, speech_ranges, to_cut_points, CutAnalysisOptions, KeepRange};
+use crate::error::AppError;
+use crate::timestamp::Timestamp;
+use crate::video_processor::CutPoint;
+use crate::whisper::load_transcript;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Settings for finding dead air in the audio signal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SilenceOptions {
+    /// Level in dBFS below which audio counts as silent
+    pub threshold_db: f64,
+    /// How far above the threshold the level must climb to end a silence
+    ///
+    /// Keeps breaths and room noise hovering around the threshold from
+    /// chopping one silence into many short ones.
+    pub hysteresis_db: f64,
+    /// Quieter stretches shorter than this are kept
+    pub min_silence_secs: f64,
+    /// Length of the windows the level is measured over
+    pub window_secs: f64,
+    pub channel: ChannelSelection,
+}
+
+impl Default for SilenceOptions {
+    fn default() -> Self {
+        SilenceOptions {
+            threshold_db: -40.0,
+            hysteresis_db: 6.0,
+            min_silence_secs: 0.5,
+            window_secs: 0.02,
+            channel: ChannelSelection::Mix,
+        }
+    }
+}
+
+/// Samples read from the audio file at a time, so long recordings aren't held in memory
+const BLOCK_SAMPLES: usize = WHISPER_SAMPLE_RATE as usize * 30;
+
+/// Find cut points that drop silences from an audio file
+///
+/// With a transcript, only audio that is both sound and inside a transcript
+/// clip is kept, so pauses between segments and dead air inside them both go.
+pub fn analyze_audio_for_cuts(
+    audio_path: &str,
+    transcript_path: Option<&str>,
+    silence: &SilenceOptions,
+    options: &CutAnalysisOptions,
+) -> Result<Vec<CutPoint>, AppError> {
+    let mut reader = AudioReader::open(Path::new(audio_path), silence.channel)?;
+    let mut detector = SilenceDetector::new(WHISPER_SAMPLE_RATE, silence);
+    let mut block = Vec::with_capacity(BLOCK_SAMPLES);
+    loop {
+        block.clear();
+        let read = reader.read(&mut block, BLOCK_SAMPLES)?;
+        detector.push(&block);
+        if read < BLOCK_SAMPLES {
+            break;
+        }
+    }
+    let (silences, total) = detector.finish();
+    let mut ranges = between(&silences, total);
+
+    if let Some(transcript_path) = transcript_path {
+        let transcript = load_transcript(transcript_path)?;
+        ranges = intersect(&ranges, &group_speech(&speech_ranges(&transcript), options));
+    }
+
+    Ok(to_cut_points(&finish_ranges(ranges, options)))
+}
+
+/// Time from zero to `total` not covered by the sorted `silences`
+fn between(silences: &[KeepRange], total: Timestamp) -> Vec<KeepRange> {
+    let mut ranges = Vec::new();
+    let mut start = Timestamp::ZERO;
+    for silence in silences {
+        if silence.start > start {
+            ranges.push(KeepRange::new(start, silence.start));
+        }
+        start = silence.end;
+    }
+    if total > start {
+        ranges.push(KeepRange::new(start, total));
+    }
+    ranges
+}
+
+/// Finds silences in audio fed to it a block at a time
+struct SilenceDetector<'a> {
+    options: &'a SilenceOptions,
+    sample_rate: u32,
+    window: usize,
+    /// Start of a window still waiting for the rest of its samples
+    partial: Vec<f32>,
+    /// Samples measured so far
+    position: usize,
+    silent_since: Option<usize>,
+    silences: Vec<KeepRange>,
+}
+
+impl<'a> SilenceDetector<'a> {
+    fn new(sample_rate: u32, options: &'a SilenceOptions) -> Self {
+        SilenceDetector {
+            options,
+            sample_rate,
+            window: ((options.window_secs * sample_rate as f64).round() as usize).max(1),
+            partial: Vec::new(),
+            position: 0,
+            silent_since: None,
+            silences: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, mut samples: &[f32]) {
+        if !self.partial.is_empty() {
+            let taken = (self.window - self.partial.len()).min(samples.len());
+            self.partial.extend_from_slice(&samples[..taken]);
+            samples = &samples[taken..];
+            if self.partial.len() < self.window {
+                return;
+            }
+            let frame = std::mem::take(&mut self.partial);
+            self.measure(&frame);
+        }
+
+        let mut frames = samples.chunks_exact(self.window);
+        for frame in &mut frames {
+            self.measure(frame);
+        }
+        self.partial.extend_from_slice(frames.remainder());
+    }
+
+    /// The silences found, and the length of the audio
+    fn finish(mut self) -> (Vec<KeepRange>, Timestamp) {
+        if !self.partial.is_empty() {
+            let frame = std::mem::take(&mut self.partial);
+            self.measure(&frame);
+        }
+        if let Some(start) = self.silent_since {
+            self.add_silence(start, self.position);
+        }
+        let total = self.time_at(self.position);
+        (self.silences, total)
+    }
+
+    fn measure(&mut self, frame: &[f32]) {
+        let level = rms_db(frame);
+        let release_db = self.options.threshold_db + self.options.hysteresis_db.max(0.0);
+        match self.silent_since {
+            None if level < self.options.threshold_db => self.silent_since = Some(self.position),
+            Some(start) if level > release_db => {
+                self.add_silence(start, self.position);
+                self.silent_since = None;
+            }
+            _ => {}
+        }
+        self.position += frame.len();
+    }
+
+    fn add_silence(&mut self, start: usize, end: usize) {
+        let range = KeepRange::new(self.time_at(start), self.time_at(end));
+        if range.duration() >= self.options.min_silence_secs {
+            self.silences.push(range);
+        }
+    }
+
+    fn time_at(&self, sample: usize) -> Timestamp {
+        Timestamp::from_secs(sample as f64 / self.sample_rate as f64)
+    }
+}
+
+/// Root mean square level of a frame in dBFS
+fn rms_db(frame: &[f32]) -> f64 {
+    let power = frame.iter().map(|&s| s as f64 * s as f64).sum::<f64>() / frame.len().max(1) as f64;
+    10.0 * power.max(1e-12).log10()
+}
+
+/// Time covered by both lists of sorted, non-overlapping ranges
+fn intersect(a: &[KeepRange], b: &[KeepRange]) -> Vec<KeepRange> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let start = if a[i].start > b[j].start { a[i].start } else { b[j].start };
+        let end = if a[i].end < b[j].end { a[i].end } else { b[j].end };
+        if end > start {
+            out.push(KeepRange::new(start, end));
+        }
+        if a[i].end < b[j].end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cuts::test_support::secs;
+
+    const RATE: u32 = 1000;
+
+    /// A square wave at `amplitude` for each (seconds, amplitude) part
+    fn signal(parts: &[(f64, f32)]) -> Vec<f32> {
+        parts
+            .iter()
+            .flat_map(|&(secs, amplitude)| {
+                (0..(secs * RATE as f64) as usize).map(move |i| if i % 2 == 0 { amplitude } else { -amplitude })
+            })
+            .collect()
+    }
+
+    /// Silences in `samples` read all at once, and the length of the audio
+    fn detect(samples: &[f32], options: &SilenceOptions) -> (Vec<KeepRange>, Timestamp) {
+        let mut detector = SilenceDetector::new(RATE, options);
+        detector.push(samples);
+        detector.finish()
+    }
+
+    #[test]
+    fn long_silences_are_removed_and_short_ones_kept() {
+        // Speech at -6 dBFS with a 0.2s and a 1.0s gap of -60 dBFS noise
+        let samples = signal(&[(1.0, 0.5), (0.2, 0.001), (1.0, 0.5), (1.0, 0.001), (0.5, 0.5)]);
+        let (silences, total) = detect(&samples, &SilenceOptions::default());
+        assert_eq!(secs(&between(&silences, total)), vec![(0.0, 2.2), (3.2, 3.7)]);
+    }
+
+    #[test]
+    fn hysteresis_holds_silence_through_noise_near_the_threshold() {
+        // -60 dBFS, then a -37 dBFS rumble just above the -40 dBFS threshold, then speech
+        let samples = signal(&[(1.0, 0.5), (0.5, 0.001), (0.5, 0.014), (0.5, 0.001), (1.0, 0.5)]);
+
+        let (silences, _) = detect(&samples, &SilenceOptions::default());
+        assert_eq!(secs(&silences), vec![(1.0, 2.5)]);
+
+        let no_hysteresis = SilenceOptions {
+            hysteresis_db: 0.0,
+            ..SilenceOptions::default()
+        };
+        let (silences, _) = detect(&samples, &no_hysteresis);
+        assert_eq!(secs(&silences), vec![(1.0, 1.5), (2.0, 2.5)]);
+    }
+
+    #[test]
+    fn silences_are_found_the_same_when_read_in_blocks() {
+        let samples = signal(&[(1.0, 0.5), (0.2, 0.001), (1.0, 0.5), (1.0, 0.001), (0.5, 0.5)]);
+        let options = SilenceOptions::default();
+
+        // Blocks that end partway through a 20-sample window
+        let mut detector = SilenceDetector::new(RATE, &options);
+        for block in samples.chunks(333) {
+            detector.push(block);
+        }
+        let (silences, total) = detector.finish();
+        assert_eq!(silences, detect(&samples, &options).0);
+        assert_eq!(secs(&between(&silences, total)), vec![(0.0, 2.2), (3.2, 3.7)]);
+    }
+
+    #[test]
+    fn intersecting_with_transcript_clips() {
+        let range = |start: f64, end: f64| KeepRange::new(Timestamp::from_secs(start), Timestamp::from_secs(end));
+        let sound = vec![range(0.0, 2.0), range(2.5, 6.0)];
+        let speech = vec![range(0.5, 3.0), range(5.0, 7.0)];
+        assert_eq!(
+            secs(&intersect(&sound, &speech)),
+            vec![(0.5, 2.0), (2.5, 3.0), (5.0, 6.0)]
+        );
+    }
+}
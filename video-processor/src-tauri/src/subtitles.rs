@@ -0,0 +1,704 @@
+use crate::captions::{segment_captions, CaptionOptions};
+use crate::error::AppError;
+use crate::timestamp::Timestamp;
+use crate::whisper::{Transcript, TranscriptSegment};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Subtitle and text formats a transcript can be written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+    Ass,
+    Ssa,
+    Ttml,
+    Txt,
+}
+
+impl SubtitleFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+            SubtitleFormat::Ass => "ass",
+            SubtitleFormat::Ssa => "ssa",
+            SubtitleFormat::Ttml => "ttml",
+            SubtitleFormat::Txt => "txt",
+        }
+    }
+}
+
+/// One caption on screen between `start` and `end`; lines are separated by `\n`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub text: String,
+}
+
+/// One cue per transcript segment, skipping segments without text
+pub fn cues_from_transcript(transcript: &Transcript) -> Vec<Cue> {
+    transcript
+        .segments
+        .iter()
+        .filter(|segment| !segment.text.trim().is_empty())
+        .map(|segment| Cue {
+            start: segment.start,
+            end: segment.end,
+            text: segment.text.trim().to_string(),
+        })
+        .collect()
+}
+
+/// Render cues in the given format
+pub fn render(cues: &[Cue], format: SubtitleFormat, language: Option<&str>) -> String {
+    match format {
+        SubtitleFormat::Srt => render_srt(cues),
+        SubtitleFormat::Vtt => render_vtt(cues),
+        SubtitleFormat::Ass => render_ass(cues, false),
+        SubtitleFormat::Ssa => render_ass(cues, true),
+        SubtitleFormat::Ttml => render_ttml(cues, language),
+        SubtitleFormat::Txt => render_txt(cues),
+    }
+}
+
+/// Write a transcript file as subtitles, returning the path written
+///
+/// With `captions` the transcript is re-split into readable cues, otherwise each
+/// segment becomes one cue. Without `output_path` the file goes next to the
+/// transcript with the format's extension.
+pub fn export_transcript(
+    transcript_path: &str,
+    format: SubtitleFormat,
+    output_path: Option<&str>,
+    captions: Option<&CaptionOptions>,
+) -> Result<String, AppError> {
+    let transcript = crate::whisper::load_transcript(transcript_path)?;
+    let output_path = match output_path {
+        Some(path) => path.to_string(),
+        None => Path::new(transcript_path)
+            .with_extension(format.extension())
+            .to_string_lossy()
+            .to_string(),
+    };
+
+    let cues = match captions {
+        Some(options) => segment_captions(&transcript, options),
+        None => cues_from_transcript(&transcript),
+    };
+    std::fs::write(&output_path, render(&cues, format, text_language(&transcript)))?;
+    Ok(output_path)
+}
+
+/// Language the transcript's text is in, which is English once it was translated
+fn text_language(transcript: &Transcript) -> Option<&str> {
+    if transcript.translated {
+        Some("en")
+    } else {
+        transcript.language.as_deref()
+    }
+}
+
+/// Split a time into whole hours, minutes, seconds and milliseconds
+fn clock(time: Timestamp) -> (u64, u64, u64, u64) {
+    let millis = (time.as_secs().max(0.0) * 1000.0).round() as u64;
+    (millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000)
+}
+
+/// `HH:MM:SS<sep>mmm`, as used by SRT (`,`), WebVTT and TTML (`.`)
+fn format_millis(time: Timestamp, separator: char) -> String {
+    let (h, m, s, ms) = clock(time);
+    format!("{:02}:{:02}:{:02}{}{:03}", h, m, s, separator, ms)
+}
+
+/// `H:MM:SS.cc`, as used by ASS and SSA
+fn format_centis(time: Timestamp) -> String {
+    let centis = (time.as_secs().max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centis / 360_000,
+        centis / 6000 % 60,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+fn render_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_millis(cue.start, ','),
+            format_millis(cue.end, ','),
+            cue.text
+        );
+    }
+    out
+}
+
+fn render_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        // Cue text is parsed as markup, and a blank line would end the cue early
+        let text = escape_markup(&cue.text)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let _ = write!(
+            out,
+            "{} --> {}\n{}\n\n",
+            format_millis(cue.start, '.'),
+            format_millis(cue.end, '.'),
+            text
+        );
+    }
+    out
+}
+
+fn render_ass(cues: &[Cue], ssa: bool) -> String {
+    let mut out = String::new();
+    if ssa {
+        out.push_str(
+            "[Script Info]\n\
+             ScriptType: v4.00\n\
+             PlayResX: 1920\n\
+             PlayResY: 1080\n\
+             \n\
+             [V4 Styles]\n\
+             Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, TertiaryColour, BackColour, \
+             Bold, Italic, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, AlphaLevel, Encoding\n\
+             Style: Default,Arial,64,16777215,255,0,0,0,0,1,3,1,2,60,60,60,0,1\n\
+             \n\
+             [Events]\n\
+             Format: Marked, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+        );
+    } else {
+        out.push_str(
+            "[Script Info]\n\
+             ScriptType: v4.00+\n\
+             PlayResX: 1920\n\
+             PlayResY: 1080\n\
+             WrapStyle: 0\n\
+             ScaledBorderAndShadow: yes\n\
+             \n\
+             [V4+ Styles]\n\
+             Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, \
+             Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, \
+             Alignment, MarginL, MarginR, MarginV, Encoding\n\
+             Style: Default,Arial,64,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,1,2,60,60,60,1\n\
+             \n\
+             [Events]\n\
+             Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+        );
+    }
+
+    let layer = if ssa { "Marked=0" } else { "0" };
+    for cue in cues {
+        // Braces would open an override block, and real line breaks would end the event
+        let text = cue.text.replace('{', "\\{").replace('}', "\\}").replace('\n', "\\N");
+        let _ = writeln!(
+            out,
+            "Dialogue: {},{},{},Default,,0,0,0,,{}",
+            layer,
+            format_centis(cue.start),
+            format_centis(cue.end),
+            text
+        );
+    }
+    out
+}
+
+fn render_ttml(cues: &[Cue], language: Option<&str>) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<tt xmlns=\"http://www.w3.org/ns/ttml\" xml:lang=\"{}\">",
+        escape_markup(language.unwrap_or("en"))
+    );
+    out.push_str("  <body>\n    <div>\n");
+    for cue in cues {
+        let text = cue.text.lines().map(escape_markup).collect::<Vec<_>>().join("<br/>");
+        let _ = writeln!(
+            out,
+            "      <p begin=\"{}\" end=\"{}\">{}</p>",
+            format_millis(cue.start, '.'),
+            format_millis(cue.end, '.'),
+            text
+        );
+    }
+    out.push_str("    </div>\n  </body>\n</tt>\n");
+    out
+}
+
+fn render_txt(cues: &[Cue]) -> String {
+    cues.iter().map(|cue| format!("{}\n", cue.text.replace('\n', " "))).collect()
+}
+
+/// Escape text for XML and WebVTT cue payloads
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Read an SRT, WebVTT or ASS/SSA file and save it as a transcript, returning the transcript path
+///
+/// Without `output_path` the transcript goes next to the subtitles as `.json`.
+pub fn import_subtitles(subtitle_path: &str, output_path: Option<&str>) -> Result<String, AppError> {
+    let content = std::fs::read_to_string(subtitle_path)?;
+    let extension = Path::new(subtitle_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let transcript = parse_subtitles(&content, extension.as_deref())?;
+
+    let output_path = match output_path {
+        Some(path) => path.to_string(),
+        None => Path::new(subtitle_path).with_extension("json").to_string_lossy().to_string(),
+    };
+    crate::whisper::save_transcript(&output_path, &transcript)?;
+    Ok(output_path)
+}
+
+/// Parse subtitles into a transcript, picking the format from the extension or the content
+pub fn parse_subtitles(content: &str, extension: Option<&str>) -> Result<Transcript, AppError> {
+    let content = content.trim_start_matches('\u{feff}');
+    let format = match extension {
+        Some("srt") => SubtitleFormat::Srt,
+        Some("vtt") => SubtitleFormat::Vtt,
+        Some("ass") => SubtitleFormat::Ass,
+        Some("ssa") => SubtitleFormat::Ssa,
+        _ if content.trim_start().starts_with("WEBVTT") => SubtitleFormat::Vtt,
+        _ if content.trim_start().starts_with("[Script Info]") => SubtitleFormat::Ass,
+        _ => SubtitleFormat::Srt,
+    };
+
+    let (cues, allow_overlaps) = match format {
+        SubtitleFormat::Vtt => (parse_vtt(content)?, false),
+        SubtitleFormat::Ass | SubtitleFormat::Ssa => {
+            // Events are often grouped by style or layer, and signs overlap dialogue
+            let mut cues = parse_ass(content)?;
+            cues.sort_by(|(_, a), (_, b)| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+            (cues, true)
+        }
+        _ => (parse_srt(content)?, false),
+    };
+    validate_cues(&cues, allow_overlaps)?;
+
+    let segments: Vec<TranscriptSegment> = cues
+        .into_iter()
+        .map(|(_, cue)| TranscriptSegment {
+            start: cue.start,
+            end: cue.end,
+            text: cue.text.lines().map(str::trim).collect::<Vec<_>>().join(" "),
+            words: Vec::new(),
+        })
+        .collect();
+    let mut transcript = Transcript {
+        segments,
+        text: String::new(),
+        language: None,
+        language_probability: None,
+        translated: false,
+    };
+    transcript.rebuild_text();
+    Ok(transcript)
+}
+
+fn subtitle_error(line: usize, message: impl std::fmt::Display) -> AppError {
+    AppError::SubtitleError(format!("Line {}: {}", line, message))
+}
+
+/// Cues must be in order, end after they start and, unless allowed, not overlap
+fn validate_cues(cues: &[(usize, Cue)], allow_overlaps: bool) -> Result<(), AppError> {
+    if cues.is_empty() {
+        return Err(AppError::SubtitleError("No cues found".to_string()));
+    }
+    for (line, cue) in cues {
+        if cue.end <= cue.start {
+            return Err(subtitle_error(*line, "cue ends before it starts"));
+        }
+    }
+    for pair in cues.windows(2) {
+        let ((_, previous), (line, cue)) = (&pair[0], &pair[1]);
+        if cue.start < previous.start {
+            return Err(subtitle_error(*line, "cue starts before the previous cue"));
+        }
+        if cue.start < previous.end && !allow_overlaps {
+            return Err(subtitle_error(
+                *line,
+                format!("cue overlaps the previous cue, which ends at {}", format_millis(previous.end, '.')),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Parse `[HH:]MM:SS[.,]fraction`, as used by every supported format
+fn parse_timecode(value: &str) -> Option<Timestamp> {
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (*h, *m, *s),
+        [m, s] => ("0", *m, *s),
+        _ => return None,
+    };
+    let (whole, fraction) = seconds.split_once(['.', ',']).unwrap_or((seconds, ""));
+
+    let number = |digits: &str| -> Option<u64> {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    let (hours, minutes, whole) = (number(hours)?, number(minutes)?, number(whole)?);
+    if minutes >= 60 || whole >= 60 {
+        return None;
+    }
+    let fraction = if fraction.is_empty() {
+        0.0
+    } else {
+        number(fraction)? as f64 / 10f64.powi(fraction.len() as i32)
+    };
+
+    Some(Timestamp::from_secs((hours * 3600 + minutes * 60 + whole) as f64 + fraction))
+}
+
+/// Parse an `start --> end [settings]` line
+fn parse_timing_line(line: &str, line_number: usize) -> Result<(Timestamp, Timestamp), AppError> {
+    let (start, rest) = line
+        .split_once("-->")
+        .ok_or_else(|| subtitle_error(line_number, format!("expected a timing line, found '{}'", line)))?;
+    // WebVTT settings and SRT coordinates can follow the end time
+    let end = rest.split_whitespace().next().unwrap_or("");
+
+    let parse = |value: &str| {
+        parse_timecode(value)
+            .ok_or_else(|| subtitle_error(line_number, format!("malformed timecode '{}'", value.trim())))
+    };
+    Ok((parse(start)?, parse(end)?))
+}
+
+/// Split into blank-line separated blocks of (line number, line) pairs
+fn blocks(content: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut blocks = Vec::new();
+    let mut current = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push((i + 1, line));
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+fn parse_srt(content: &str) -> Result<Vec<(usize, Cue)>, AppError> {
+    let mut cues = Vec::new();
+    for block in blocks(content) {
+        // The counter line is optional in practice
+        let timing = if block[0].1.contains("-->") { 0 } else { 1 };
+        let (line_number, timing_line) = *block
+            .get(timing)
+            .ok_or_else(|| subtitle_error(block[0].0, "cue has no timing line"))?;
+        let (start, end) = parse_timing_line(timing_line, line_number)?;
+        let text = block[timing + 1..].iter().map(|(_, line)| strip_tags(line)).collect::<Vec<_>>();
+        cues.push((line_number, Cue { start, end, text: text.join("\n") }));
+    }
+    Ok(cues)
+}
+
+fn parse_vtt(content: &str) -> Result<Vec<(usize, Cue)>, AppError> {
+    let mut blocks = blocks(content).into_iter();
+    match blocks.next() {
+        Some(header) if header[0].1.starts_with("WEBVTT") => {}
+        _ => return Err(AppError::SubtitleError("Missing WEBVTT header".to_string())),
+    }
+
+    let mut cues = Vec::new();
+    for block in blocks {
+        let first = block[0].1;
+        if first.starts_with("NOTE") || first.starts_with("STYLE") || first.starts_with("REGION") {
+            continue;
+        }
+        // An identifier line may precede the timing line
+        let timing = if first.contains("-->") { 0 } else { 1 };
+        let (line_number, timing_line) = *block
+            .get(timing)
+            .ok_or_else(|| subtitle_error(block[0].0, "cue has no timing line"))?;
+        let (start, end) = parse_timing_line(timing_line, line_number)?;
+        let text = block[timing + 1..]
+            .iter()
+            .map(|(_, line)| unescape_markup(&strip_tags(line)))
+            .collect::<Vec<_>>();
+        cues.push((line_number, Cue { start, end, text: text.join("\n") }));
+    }
+    Ok(cues)
+}
+
+fn parse_ass(content: &str) -> Result<Vec<(usize, Cue)>, AppError> {
+    let mut in_events = false;
+    let mut format: Vec<String> = Vec::new();
+    let mut cues = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[Events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = fields.split(',').map(|f| f.trim().to_ascii_lowercase()).collect();
+            continue;
+        }
+        let Some(fields) = line.strip_prefix("Dialogue:") else {
+            continue;
+        };
+        if format.is_empty() {
+            return Err(subtitle_error(line_number, "Dialogue before the Format line"));
+        }
+
+        // Text is always the last field and may itself contain commas
+        let values: Vec<&str> = fields.splitn(format.len(), ',').map(str::trim).collect();
+        let field = |name: &str| {
+            format
+                .iter()
+                .position(|f| f == name)
+                .and_then(|i| values.get(i).copied())
+                .ok_or_else(|| subtitle_error(line_number, format!("Dialogue has no {} field", name)))
+        };
+        let timecode = |name: &str| -> Result<Timestamp, AppError> {
+            let value = field(name)?;
+            parse_timecode(value).ok_or_else(|| subtitle_error(line_number, format!("malformed timecode '{}'", value)))
+        };
+
+        let text = strip_ass_overrides(field("text")?);
+        if text.trim().is_empty() {
+            continue;
+        }
+        cues.push((
+            line_number,
+            Cue {
+                start: timecode("start")?,
+                end: timecode("end")?,
+                text,
+            },
+        ));
+    }
+    Ok(cues)
+}
+
+/// Drop `{...}` override blocks and turn ASS line break codes into text
+fn strip_ass_overrides(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    let mut depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some('N') | Some('n') => {
+                    chars.next();
+                    if depth == 0 {
+                        out.push('\n');
+                    }
+                }
+                Some('h') => {
+                    chars.next();
+                    if depth == 0 {
+                        out.push(' ');
+                    }
+                }
+                Some(&brace @ ('{' | '}')) => {
+                    chars.next();
+                    if depth == 0 {
+                        out.push(brace);
+                    }
+                }
+                _ if depth == 0 => out.push(c),
+                _ => {}
+            },
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join("\n")
+}
+
+/// Remove `<i>`, `<font ...>` and WebVTT voice/class tags
+fn strip_tags(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+fn unescape_markup(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cues() -> Vec<Cue> {
+        vec![
+            Cue {
+                start: Timestamp::from_secs(1.5),
+                end: Timestamp::from_secs(3.0),
+                text: "Hello {world} & <you>".to_string(),
+            },
+            Cue {
+                start: Timestamp::from_secs(3661.234),
+                end: Timestamp::from_secs(3662.0),
+                text: "Two\nlines".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn renders_srt_and_vtt() {
+        assert_eq!(
+            render(&cues(), SubtitleFormat::Srt, None),
+            "1\n00:00:01,500 --> 00:00:03,000\nHello {world} & <you>\n\n\
+             2\n01:01:01,234 --> 01:01:02,000\nTwo\nlines\n\n"
+        );
+        assert_eq!(
+            render(&cues(), SubtitleFormat::Vtt, None),
+            "WEBVTT\n\n\
+             00:00:01.500 --> 00:00:03.000\nHello {world} &amp; &lt;you&gt;\n\n\
+             01:01:01.234 --> 01:01:02.000\nTwo\nlines\n\n"
+        );
+    }
+
+    #[test]
+    fn renders_ass_and_ssa_events() {
+        let ass = render(&cues(), SubtitleFormat::Ass, None);
+        assert!(ass.contains("ScriptType: v4.00+"));
+        assert!(ass.contains("Dialogue: 0,0:00:01.50,0:00:03.00,Default,,0,0,0,,Hello \\{world\\} & <you>\n"));
+        assert!(ass.contains("Dialogue: 0,1:01:01.23,1:01:02.00,Default,,0,0,0,,Two\\Nlines\n"));
+
+        let ssa = render(&cues(), SubtitleFormat::Ssa, None);
+        assert!(ssa.contains("[V4 Styles]"));
+        assert!(ssa.contains("Dialogue: Marked=0,0:00:01.50,"));
+    }
+
+    #[test]
+    fn renders_ttml_and_text() {
+        let ttml = render(&cues(), SubtitleFormat::Ttml, Some("de"));
+        assert!(ttml.contains("xml:lang=\"de\""));
+        assert!(ttml.contains("<p begin=\"00:00:01.500\" end=\"00:00:03.000\">Hello {world} &amp; &lt;you&gt;</p>"));
+        assert!(ttml.contains(">Two<br/>lines</p>"));
+
+        assert_eq!(render(&cues(), SubtitleFormat::Txt, None), "Hello {world} & <you>\nTwo lines\n");
+    }
+
+    #[test]
+    fn translated_transcripts_are_labelled_english() {
+        let mut transcript = Transcript {
+            segments: Vec::new(),
+            text: String::new(),
+            language: Some("de".to_string()),
+            language_probability: Some(0.9),
+            translated: false,
+        };
+        assert_eq!(text_language(&transcript), Some("de"));
+
+        transcript.translated = true;
+        let ttml = render(&cues(), SubtitleFormat::Ttml, text_language(&transcript));
+        assert!(ttml.contains("xml:lang=\"en\""));
+    }
+
+    fn texts(transcript: &Transcript) -> Vec<&str> {
+        transcript.segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn imports_srt_and_round_trips_export() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>Hello</i>\r\nthere\r\n\r\n\
+                   2\r\n00:00:03,000 --> 00:00:04,000 X1:10 X2:20\r\nAgain\r\n";
+        let transcript = parse_subtitles(srt, Some("srt")).unwrap();
+        assert_eq!(texts(&transcript), ["Hello there", "Again"]);
+        assert_eq!(transcript.segments[0].end.as_secs(), 2.5);
+
+        let exported = render(&cues_from_transcript(&transcript), SubtitleFormat::Srt, None);
+        let reimported = parse_subtitles(&exported, None).unwrap();
+        assert_eq!(texts(&reimported), texts(&transcript));
+    }
+
+    #[test]
+    fn imports_vtt_skipping_notes_and_identifiers() {
+        let vtt = "WEBVTT - captions\n\nNOTE written by hand\n\nintro\n00:01.000 --> 00:02.000 align:start\n\
+                   <v Ann>Fish &amp; chips</v>\n\n01:00:00.000 --> 01:00:01.250\nLater\n";
+        let transcript = parse_subtitles(vtt, None).unwrap();
+        assert_eq!(texts(&transcript), ["Fish & chips", "Later"]);
+        assert_eq!(transcript.segments[1].start.as_secs(), 3600.0);
+    }
+
+    #[test]
+    fn imports_ass_dialogue() {
+        let ass = "[Script Info]\nScriptType: v4.00+\n\n[Events]\n\
+                   Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Comment: 0,0:00:00.00,0:00:01.00,Default,,0,0,0,,ignored\n\
+                   Dialogue: 0,0:00:01.50,0:00:03.00,Default,,0,0,0,,{\\i1}Well, hi{\\i0}\\Nthere\n";
+        let transcript = parse_subtitles(ass, Some("ass")).unwrap();
+        assert_eq!(texts(&transcript), ["Well, hi there"]);
+        assert_eq!(transcript.segments[0].start.as_secs(), 1.5);
+    }
+
+    #[test]
+    fn imports_ass_events_out_of_order_and_overlapping() {
+        let ass = "[Script Info]\nScriptType: v4.00+\n\n[Events]\n\
+                   Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Dialogue: 0,0:00:04.00,0:00:05.00,Default,,0,0,0,,Second line\n\
+                   Dialogue: 0,0:00:01.00,0:00:03.00,Default,,0,0,0,,First line\n\
+                   Dialogue: 1,0:00:00.50,0:00:06.00,Sign,,0,0,0,,Main Street\n";
+        let transcript = parse_subtitles(ass, Some("ass")).unwrap();
+        assert_eq!(texts(&transcript), ["Main Street", "First line", "Second line"]);
+    }
+
+    #[test]
+    fn reports_invalid_subtitles() {
+        let error = |content: &str| match parse_subtitles(content, Some("srt")) {
+            Err(AppError::SubtitleError(message)) => message,
+            other => panic!("expected a subtitle error, got {:?}", other.map(|t| t.text)),
+        };
+
+        assert_eq!(error("1\n00:00:01,000 --> 00:00:6x,000\nHi\n"), "Line 2: malformed timecode '00:00:6x,000'");
+        assert!(error("1\n00:00:01,000 --> 00:00:03,000\nA\n\n2\n00:00:02,000 --> 00:00:04,000\nB\n")
+            .starts_with("Line 6: cue overlaps"));
+        assert!(error("1\n00:00:05,000 --> 00:00:06,000\nA\n\n2\n00:00:01,000 --> 00:00:02,000\nB\n")
+            .starts_with("Line 6: cue starts before"));
+        assert!(error("1\n00:00:02,000 --> 00:00:01,000\nA\n").contains("ends before it starts"));
+        assert_eq!(error(""), "No cues found");
+    }
+}
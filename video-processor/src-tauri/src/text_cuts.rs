@@ -0,0 +1,208 @@
+use crate::cuts::{removal_range, subtract, to_cut_points, KeepRange};
+use crate::error::AppError;
+use crate::timestamp::Timestamp;
+use crate::video_processor::CutPoint;
+use crate::whisper::{load_transcript, Transcript};
+use serde::{Deserialize, Serialize};
+
+/// Part of a transcript marked for removal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Deletion {
+    /// Segments `first` to `last`, inclusive
+    Segments { first: usize, last: usize },
+    /// Words `first_word` to `last_word` of one segment, inclusive
+    #[serde(rename_all = "camelCase")]
+    Words {
+        segment: usize,
+        first_word: usize,
+        last_word: usize,
+    },
+}
+
+/// How deleted text turns into cuts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TextCutOptions {
+    /// See `removal_range`
+    pub padding_secs: f64,
+    /// Stretches left between two deletions shorter than this are cut as well
+    pub min_keep_secs: f64,
+    /// Length of the media, so anything after the last word is kept
+    pub media_duration_secs: Option<f64>,
+}
+
+impl Default for TextCutOptions {
+    fn default() -> Self {
+        TextCutOptions {
+            padding_secs: 0.05,
+            min_keep_secs: 0.2,
+            media_duration_secs: None,
+        }
+    }
+}
+
+/// Something that can be deleted: a word, or a whole segment without word timings
+struct Unit {
+    start: Timestamp,
+    end: Timestamp,
+    deleted: bool,
+}
+
+/// Turn text deleted from a transcript file into cut points for `process_video`
+pub fn cut_points_from_text_file(
+    transcript_path: &str,
+    deletions: &[Deletion],
+    options: &TextCutOptions,
+) -> Result<Vec<CutPoint>, AppError> {
+    let transcript = load_transcript(transcript_path)?;
+    cut_points_from_text(&transcript, deletions, options)
+}
+
+/// Keep everything except the deleted text
+pub fn cut_points_from_text(
+    transcript: &Transcript,
+    deletions: &[Deletion],
+    options: &TextCutOptions,
+) -> Result<Vec<CutPoint>, AppError> {
+    // Where each segment's units start in the flat list
+    let mut offsets = Vec::with_capacity(transcript.segments.len());
+    let mut units = Vec::new();
+    for segment in &transcript.segments {
+        offsets.push(units.len());
+        if segment.words.is_empty() {
+            units.push(Unit { start: segment.start, end: segment.end, deleted: false });
+        } else {
+            units.extend(segment.words.iter().map(|w| Unit { start: w.start, end: w.end, deleted: false }));
+        }
+    }
+
+    let count = transcript.segments.len();
+    for deletion in deletions {
+        let (first, last) = match *deletion {
+            Deletion::Segments { first, last } => {
+                if last >= count {
+                    return Err(segment_error(last, count));
+                }
+                let end = offsets.get(last + 1).copied().unwrap_or(units.len());
+                (offsets[first.min(last)], end - 1)
+            }
+            Deletion::Words { segment, first_word, last_word } => {
+                let target = transcript.segments.get(segment).ok_or_else(|| segment_error(segment, count))?;
+                if last_word >= target.words.len() {
+                    return Err(AppError::TranscriptError(format!(
+                        "Segment {} has no word {}, it has {}",
+                        segment,
+                        last_word,
+                        target.words.len()
+                    )));
+                }
+                (offsets[segment] + first_word.min(last_word), offsets[segment] + last_word)
+            }
+        };
+        for unit in &mut units[first..=last] {
+            unit.deleted = true;
+        }
+    }
+
+    // Neighbouring deletions join into one run, so only one cut is made for them
+    let mut removals = Vec::new();
+    let mut i = 0;
+    while i < units.len() {
+        if !units[i].deleted {
+            i += 1;
+            continue;
+        }
+        let first = i;
+        while i < units.len() && units[i].deleted {
+            i += 1;
+        }
+        let previous_end = first.checked_sub(1).map(|p| units[p].end);
+        let next_start = units.get(i).map(|u| u.start);
+        if let Some(range) = removal_range(units[first].start, units[i - 1].end, previous_end, next_start, options.padding_secs)
+        {
+            removals.push(range);
+        }
+    }
+
+    let mut end = units.iter().map(|u| u.end).fold(Timestamp::ZERO, |a, b| if b > a { b } else { a });
+    if let Some(duration) = options.media_duration_secs {
+        end = Timestamp::from_secs(duration.max(end.as_secs()));
+    }
+    let mut kept = subtract(&[KeepRange::new(Timestamp::ZERO, end)], &removals);
+    kept.retain(|range| range.start == Timestamp::ZERO || range.end == end || range.duration() >= options.min_keep_secs);
+
+    Ok(to_cut_points(&kept))
+}
+
+fn segment_error(index: usize, count: usize) -> AppError {
+    AppError::TranscriptError(format!("No segment {}, the transcript has {}", index, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cuts::test_support::{ranges, transcript};
+
+    #[test]
+    fn deleted_words_are_cut_at_padded_word_boundaries() {
+        let t = transcript(&["This is really very good.", "Thanks for watching."]);
+        let deletions = [Deletion::Words { segment: 0, first_word: 2, last_word: 3 }];
+        let cuts = cut_points_from_text(&t, &deletions, &TextCutOptions::default()).unwrap();
+        // "is" ends at 0.9s and "good." starts at 2.0s
+        assert_eq!(ranges(&cuts), vec![(0.0, 0.95), (1.95, 3.9)]);
+
+        let options = TextCutOptions {
+            media_duration_secs: Some(10.0),
+            ..TextCutOptions::default()
+        };
+        let cuts = cut_points_from_text(&t, &deletions, &options).unwrap();
+        assert_eq!(cuts.last().unwrap().end_time, 10.0);
+    }
+
+    #[test]
+    fn adjacent_deletions_and_slivers_merge_into_one_cut() {
+        let t = transcript(&["One two.", "Three.", "Four five six."]);
+        let deletions = [
+            Deletion::Words { segment: 0, first_word: 1, last_word: 1 },
+            Deletion::Segments { first: 1, last: 1 },
+            Deletion::Words { segment: 2, first_word: 1, last_word: 2 },
+        ];
+        let options = TextCutOptions {
+            min_keep_secs: 0.6,
+            ..TextCutOptions::default()
+        };
+        // "Four" would be left as a 0.5s sliver between two cuts
+        let cuts = cut_points_from_text(&t, &deletions, &options).unwrap();
+        assert_eq!(ranges(&cuts), vec![(0.0, 0.45)]);
+
+        let cuts = cut_points_from_text(&t, &deletions, &TextCutOptions::default()).unwrap();
+        assert_eq!(ranges(&cuts), vec![(0.0, 0.45), (1.45, 1.95)]);
+    }
+
+    #[test]
+    fn out_of_range_deletions_are_rejected() {
+        let t = transcript(&["Just one segment."]);
+        let bad_word = [Deletion::Words { segment: 0, first_word: 0, last_word: 3 }];
+        assert!(matches!(cut_points_from_text(&t, &bad_word, &TextCutOptions::default()), Err(AppError::TranscriptError(_))));
+        let bad_segment = [Deletion::Segments { first: 0, last: 1 }];
+        assert!(matches!(cut_points_from_text(&t, &bad_segment, &TextCutOptions::default()), Err(AppError::TranscriptError(_))));
+    }
+
+    #[test]
+    fn deleting_a_short_word_with_touching_timings_removes_all_of_it() {
+        let mut t = transcript(&["I think a lot."]);
+        // Whisper reports contiguous timings, and "a" lasts only 80ms
+        for (i, (start, end)) in [(0.0, 0.2), (0.2, 0.5), (0.5, 0.58), (0.58, 0.9)].into_iter().enumerate() {
+            t.segments[0].words[i].start = Timestamp::from_secs(start);
+            t.segments[0].words[i].end = Timestamp::from_secs(end);
+        }
+        let deletions = [Deletion::Words { segment: 0, first_word: 2, last_word: 2 }];
+        let options = TextCutOptions {
+            min_keep_secs: 0.0,
+            ..TextCutOptions::default()
+        };
+        let cuts = cut_points_from_text(&t, &deletions, &options).unwrap();
+        assert_eq!(ranges(&cuts), vec![(0.0, 0.5), (0.58, 0.9)]);
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Sub};
+
+/// A position or duration in media time, stored in seconds
+///
+/// Serializes as a plain number of seconds so transcript JSON stays readable
+/// and matches what the frontend and `CutPoint` use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Timestamp(f64);
+
+impl Timestamp {
+    pub const ZERO: Timestamp = Timestamp(0.0);
+
+    pub fn from_secs(secs: f64) -> Self {
+        Timestamp(secs)
+    }
+
+    /// Convert whisper.cpp's segment and token times, which are in centiseconds
+    pub fn from_centiseconds(centis: i64) -> Self {
+        Timestamp(centis as f64 / 100.0)
+    }
+
+    pub fn as_secs(self) -> f64 {
+        self.0
+    }
+}
+
+impl Add for Timestamp {
+    type Output = Timestamp;
+
+    fn add(self, rhs: Timestamp) -> Timestamp {
+        Timestamp(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Timestamp {
+    type Output = Timestamp;
+
+    fn sub(self, rhs: Timestamp) -> Timestamp {
+        Timestamp(self.0 - rhs.0)
+    }
+}
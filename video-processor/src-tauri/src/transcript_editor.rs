@@ -0,0 +1,873 @@
+use crate::error::AppError;
+use crate::timestamp::Timestamp;
+use crate::whisper::{load_transcript, save_transcript, Transcript, TranscriptSegment, TranscriptWord};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Versions kept per transcript before the oldest are dropped
+const MAX_VERSIONS: usize = 100;
+
+/// Versions store only their edit, except every this many, which keep the whole transcript
+const SNAPSHOT_EVERY: usize = 20;
+
+/// Lines a history file may hold beyond its versions before it is written again from scratch
+const COMPACT_AFTER_LINES: usize = 500;
+
+/// A single change to a transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranscriptEdit {
+    /// Replace a segment's text, keeping the timings of words that didn't change
+    EditText { segment: usize, text: String },
+    /// Split a segment in two before its `word`-th word
+    Split { segment: usize, word: usize },
+    /// Join a segment with the one after it
+    Merge { segment: usize },
+    /// Move a segment, stretching its word timings to match
+    SetTiming {
+        segment: usize,
+        start: Timestamp,
+        end: Timestamp,
+    },
+}
+
+impl TranscriptEdit {
+    fn describe(&self) -> String {
+        match self {
+            TranscriptEdit::EditText { segment, .. } => format!("Edit text of segment {}", segment + 1),
+            TranscriptEdit::Split { segment, .. } => format!("Split segment {}", segment + 1),
+            TranscriptEdit::Merge { segment } => format!("Merge segments {} and {}", segment + 1, segment + 2),
+            TranscriptEdit::SetTiming { segment, .. } => format!("Adjust timing of segment {}", segment + 1),
+        }
+    }
+}
+
+/// Summary of one entry in a transcript's history
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptVersion {
+    /// Stays the same when older versions are dropped, usable in `restore`
+    pub id: u64,
+    pub description: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub current: bool,
+}
+
+/// How a version differs from the one before it
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Change {
+    /// The whole transcript, for the first version, restores and every `SNAPSHOT_EVERY` edits
+    Snapshot { transcript: Transcript },
+    Edit { edit: TranscriptEdit },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Version {
+    id: u64,
+    description: String,
+    created_at: u64,
+    change: Change,
+}
+
+/// One line of a history file
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+enum LogEntry {
+    /// A new version on top of the current one, dropping anything that was undone
+    Version(Version),
+    /// Undo or redo moved to another version
+    Current { id: u64 },
+}
+
+/// The versions of one transcript, oldest first, with a cursor for undo and redo
+pub struct EditHistory {
+    versions: Vec<Version>,
+    current: usize,
+    /// The current version's transcript, rebuilt from the nearest snapshot on undo and redo
+    transcript: Transcript,
+    next_id: u64,
+    /// Versions the last change dropped, kept until the next checkpoint so it can be rolled back
+    dropped: Vec<Version>,
+    /// Entries not yet written to the history file
+    pending: Vec<LogEntry>,
+    /// Lines in the history file, or `None` if it has to be written from scratch
+    logged_lines: Option<usize>,
+}
+
+/// Where a history stood before a change, see `EditHistory::rollback`
+pub struct Checkpoint {
+    current_id: u64,
+    next_id: u64,
+}
+
+impl EditHistory {
+    pub fn new(transcript: Transcript) -> Self {
+        let version = Version {
+            id: 0,
+            description: "Opened".to_string(),
+            created_at: now(),
+            change: Change::Snapshot { transcript: transcript.clone() },
+        };
+        EditHistory {
+            pending: vec![LogEntry::Version(version.clone())],
+            versions: vec![version],
+            current: 0,
+            transcript,
+            next_id: 1,
+            dropped: Vec::new(),
+            logged_lines: None,
+        }
+    }
+
+    pub fn current(&self) -> &Transcript {
+        &self.transcript
+    }
+
+    /// Apply an edit on top of the current version, discarding anything that was undone
+    pub fn apply(&mut self, edit: &TranscriptEdit) -> Result<&Transcript, AppError> {
+        let mut transcript = self.transcript.clone();
+        apply_edit(&mut transcript, edit)?;
+        self.push(Change::Edit { edit: edit.clone() }, transcript, edit.describe());
+        Ok(self.current())
+    }
+
+    pub fn undo(&mut self) -> Result<&Transcript, AppError> {
+        if self.current == 0 {
+            return Err(AppError::TranscriptError("Nothing to undo".to_string()));
+        }
+        self.move_to(self.current - 1)?;
+        Ok(self.current())
+    }
+
+    pub fn redo(&mut self) -> Result<&Transcript, AppError> {
+        if self.current + 1 >= self.versions.len() {
+            return Err(AppError::TranscriptError("Nothing to redo".to_string()));
+        }
+        self.move_to(self.current + 1)?;
+        Ok(self.current())
+    }
+
+    /// Bring back an earlier version as a new version, so the restore itself can be undone
+    pub fn restore(&mut self, id: u64) -> Result<&Transcript, AppError> {
+        let index = self
+            .versions
+            .iter()
+            .position(|version| version.id == id)
+            .ok_or_else(|| AppError::TranscriptError(format!("No version {}", id)))?;
+        let transcript = self.transcript_at(index)?;
+        self.push(
+            Change::Snapshot { transcript: transcript.clone() },
+            transcript,
+            format!("Restored version {}", id),
+        );
+        Ok(self.current())
+    }
+
+    pub fn versions(&self) -> Vec<TranscriptVersion> {
+        self.versions
+            .iter()
+            .enumerate()
+            .map(|(index, version)| TranscriptVersion {
+                id: version.id,
+                description: version.description.clone(),
+                created_at: version.created_at,
+                current: index == self.current,
+            })
+            .collect()
+    }
+
+    /// Remember the current state so a change made after it can be undone without a trace
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.dropped.clear();
+        Checkpoint {
+            current_id: self.versions[self.current].id,
+            next_id: self.next_id,
+        }
+    }
+
+    /// Go back to a checkpoint, forgetting versions added since and bringing back dropped ones
+    ///
+    /// Entries for the forgotten change are not written to the history file.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.versions.retain(|version| version.id < checkpoint.next_id);
+        self.versions.append(&mut self.dropped);
+        self.versions.sort_by_key(|version| version.id);
+        self.next_id = checkpoint.next_id;
+        self.pending.clear();
+        if let Some(index) = self.versions.iter().position(|version| version.id == checkpoint.current_id) {
+            if let Ok(transcript) = self.transcript_at(index) {
+                self.current = index;
+                self.transcript = transcript;
+            }
+        }
+    }
+
+    /// Add a version built from `transcript`, storing only the edit unless a snapshot is due
+    fn push(&mut self, change: Change, transcript: Transcript, description: String) {
+        let edits_since_snapshot = self.versions[..=self.current]
+            .iter()
+            .rev()
+            .take_while(|version| matches!(version.change, Change::Edit { .. }))
+            .count();
+        let change = match change {
+            Change::Edit { .. } if edits_since_snapshot + 1 >= SNAPSHOT_EVERY => {
+                Change::Snapshot { transcript: transcript.clone() }
+            }
+            change => change,
+        };
+        let version = Version {
+            id: self.next_id,
+            description,
+            created_at: now(),
+            change,
+        };
+        self.pending.push(LogEntry::Version(version.clone()));
+        self.add(version, transcript);
+    }
+
+    /// Put `version` after the current one and make it current
+    fn add(&mut self, version: Version, transcript: Transcript) {
+        self.next_id = self.next_id.max(version.id + 1);
+        self.dropped.extend(self.versions.drain(self.current + 1..));
+        self.versions.push(version);
+        self.current = self.versions.len() - 1;
+        self.transcript = transcript;
+
+        if self.versions.len() > MAX_VERSIONS {
+            // The new oldest version must stand on its own
+            if matches!(self.versions[1].change, Change::Edit { .. }) {
+                if let Ok(transcript) = self.transcript_at(1) {
+                    self.versions[1].change = Change::Snapshot { transcript };
+                }
+            }
+            self.dropped.push(self.versions.remove(0));
+            self.current -= 1;
+        }
+    }
+
+    fn move_to(&mut self, index: usize) -> Result<(), AppError> {
+        self.transcript = self.transcript_at(index)?;
+        self.current = index;
+        self.pending.push(LogEntry::Current { id: self.versions[index].id });
+        Ok(())
+    }
+
+    /// Rebuild a version's transcript by replaying edits onto the snapshot before it
+    fn transcript_at(&self, index: usize) -> Result<Transcript, AppError> {
+        let base = self.versions[..=index]
+            .iter()
+            .rposition(|version| matches!(version.change, Change::Snapshot { .. }))
+            .ok_or_else(|| AppError::TranscriptError("Edit history has no snapshot to start from".to_string()))?;
+        let mut transcript = match &self.versions[base].change {
+            Change::Snapshot { transcript } => transcript.clone(),
+            Change::Edit { .. } => unreachable!("found by looking for a snapshot"),
+        };
+        for version in &self.versions[base + 1..=index] {
+            if let Change::Edit { edit } = &version.change {
+                apply_edit(&mut transcript, edit)?;
+            }
+        }
+        Ok(transcript)
+    }
+
+    /// Rebuild a history from the lines of its file, ignoring a damaged tail
+    fn replay(log: &str) -> Option<Self> {
+        let mut history: Option<EditHistory> = None;
+        let mut lines = 0;
+        for line in log.lines() {
+            let Ok(entry) = serde_json::from_str::<LogEntry>(line) else {
+                break;
+            };
+            match (entry, history.as_mut()) {
+                (LogEntry::Version(version), None) => {
+                    let Change::Snapshot { transcript } = &version.change else {
+                        return None;
+                    };
+                    let mut started = EditHistory::new(transcript.clone());
+                    started.versions = vec![version];
+                    started.next_id = started.versions[0].id + 1;
+                    started.pending.clear();
+                    history = Some(started);
+                }
+                (LogEntry::Version(version), Some(history)) => {
+                    let transcript = match &version.change {
+                        Change::Snapshot { transcript } => transcript.clone(),
+                        Change::Edit { edit } => {
+                            let mut transcript = history.transcript.clone();
+                            apply_edit(&mut transcript, edit).ok()?;
+                            transcript
+                        }
+                    };
+                    history.add(version, transcript);
+                }
+                (LogEntry::Current { id }, Some(history)) => {
+                    let index = history.versions.iter().position(|version| version.id == id)?;
+                    history.transcript = history.transcript_at(index).ok()?;
+                    history.current = index;
+                }
+                (LogEntry::Current { .. }, None) => return None,
+            }
+            lines += 1;
+        }
+
+        let mut history = history?;
+        history.dropped.clear();
+        // A damaged tail is dropped by writing the file again
+        history.logged_lines = (lines == log.lines().count()).then_some(lines);
+        Some(history)
+    }
+
+    /// Every version and the cursor, for writing the history file from scratch
+    fn log(&self) -> Vec<LogEntry> {
+        self.versions
+            .iter()
+            .cloned()
+            .map(LogEntry::Version)
+            .chain([LogEntry::Current { id: self.versions[self.current].id }])
+            .collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Where a transcript's edit history is kept, next to the transcript itself
+///
+/// One JSON entry per line, appended as the transcript is edited.
+pub fn history_path(transcript_path: &str) -> String {
+    format!("{}.history.jsonl", transcript_path)
+}
+
+/// Load a transcript with its saved history, or start a history if there is none
+///
+/// A transcript changed outside the editor since the history was saved is added
+/// as a new version, so the earlier ones can still be restored.
+fn load_history(path: &str) -> Result<EditHistory, AppError> {
+    let transcript = load_transcript(path)?;
+    let saved = fs::read_to_string(history_path(path))
+        .ok()
+        .and_then(|log| EditHistory::replay(&log));
+
+    let Some(mut history) = saved else {
+        return Ok(EditHistory::new(transcript));
+    };
+    let unchanged = serde_json::to_value(history.current()).ok() == serde_json::to_value(&transcript).ok();
+    if !unchanged {
+        history.push(
+            Change::Snapshot { transcript: transcript.clone() },
+            transcript,
+            "Changed outside the editor".to_string(),
+        );
+    }
+    Ok(history)
+}
+
+/// Append the history's new entries to its file, rewriting the file once it holds too much undone history
+///
+/// If writing fails the whole file is written again on the next save, so no entry is lost.
+fn save_history(path: &str, history: &mut EditHistory) -> Result<(), AppError> {
+    let pending = std::mem::take(&mut history.pending);
+    let lines = history.logged_lines.take().map(|lines| lines + pending.len());
+    match lines {
+        Some(lines) if lines <= history.versions.len() + COMPACT_AFTER_LINES => {
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(history_path(path))?
+                .write_all(log_text(&pending)?.as_bytes())?;
+            history.logged_lines = Some(lines);
+        }
+        _ => {
+            let log = history.log();
+            // Write beside the file and rename, so a crash can't lose the whole history
+            let temp_path = format!("{}.tmp", history_path(path));
+            fs::write(&temp_path, log_text(&log)?)?;
+            fs::rename(&temp_path, history_path(path))?;
+            history.logged_lines = Some(log.len());
+        }
+    }
+    Ok(())
+}
+
+/// History entries as JSON, one per line
+fn log_text(entries: &[LogEntry]) -> Result<String, AppError> {
+    let mut text = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| AppError::TranscriptError(format!("Failed to serialize edit history: {}", e)))?;
+        text.push_str(&line);
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+/// Load a transcript's history and write back what loading added to it
+fn start_session(path: &str) -> Result<EditHistory, AppError> {
+    let mut history = load_history(path)?;
+    if let Err(e) = save_history(path, &mut history) {
+        eprintln!("Failed to save edit history for {}: {}", path, e);
+    }
+    Ok(history)
+}
+
+/// Transcripts open for editing, keyed by file path
+///
+/// Held in managed Tauri state. Every change is written back to the transcript
+/// file so cut analysis and rendering see it, and appended to a history file
+/// next to it so undo survives a restart.
+#[derive(Default)]
+pub struct TranscriptEditor {
+    sessions: Mutex<HashMap<String, EditHistory>>,
+}
+
+impl TranscriptEditor {
+    /// Load a transcript for editing, picking up its saved history
+    pub fn open(&self, path: &str) -> Result<Transcript, AppError> {
+        let history = start_session(path)?;
+        let transcript = history.current().clone();
+        self.lock_sessions().insert(path.to_string(), history);
+        Ok(transcript)
+    }
+
+    /// Run `change` on the transcript's history and save the resulting version
+    ///
+    /// If the transcript can't be saved the history goes back to how it was.
+    pub fn update(
+        &self,
+        path: &str,
+        change: impl FnOnce(&mut EditHistory) -> Result<&Transcript, AppError>,
+    ) -> Result<Transcript, AppError> {
+        self.with_history(path, |history| {
+            let checkpoint = history.checkpoint();
+            let transcript = change(history)?.clone();
+            if let Err(e) = save_transcript(path, &transcript) {
+                history.rollback(checkpoint);
+                return Err(e);
+            }
+            if let Err(e) = save_history(path, history) {
+                eprintln!("Failed to save edit history for {}: {}", path, e);
+            }
+            Ok(transcript)
+        })
+    }
+
+    pub fn versions(&self, path: &str) -> Result<Vec<TranscriptVersion>, AppError> {
+        self.with_history(path, |history| Ok(history.versions()))
+    }
+
+    /// Run `f` on a transcript's history, opening the transcript first if needed
+    fn with_history<T>(
+        &self,
+        path: &str,
+        f: impl FnOnce(&mut EditHistory) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        let mut sessions = self.lock_sessions();
+        if !sessions.contains_key(path) {
+            sessions.insert(path.to_string(), start_session(path)?);
+        }
+        f(sessions.get_mut(path).expect("session was just inserted"))
+    }
+
+    fn lock_sessions(&self) -> std::sync::MutexGuard<'_, HashMap<String, EditHistory>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn segment_error(index: usize, count: usize) -> AppError {
+    AppError::TranscriptError(format!("No segment {}, the transcript has {}", index, count))
+}
+
+/// Apply one edit to a transcript in place
+pub fn apply_edit(transcript: &mut Transcript, edit: &TranscriptEdit) -> Result<(), AppError> {
+    let count = transcript.segments.len();
+    match edit {
+        TranscriptEdit::EditText { segment, text } => {
+            let target = transcript.segments.get_mut(*segment).ok_or_else(|| segment_error(*segment, count))?;
+            edit_text(target, text);
+        }
+        TranscriptEdit::Split { segment, word } => {
+            let target = transcript.segments.get(*segment).ok_or_else(|| segment_error(*segment, count))?;
+            let (left, right) = split_segment(target, *word)?;
+            transcript.segments.splice(*segment..=*segment, [left, right]);
+        }
+        TranscriptEdit::Merge { segment } => {
+            if *segment + 1 >= count {
+                return Err(AppError::TranscriptError(format!(
+                    "Segment {} has no following segment to merge with",
+                    segment
+                )));
+            }
+            let next = transcript.segments.remove(*segment + 1);
+            let target = &mut transcript.segments[*segment];
+            target.end = next.end;
+            target.text = format!("{} {}", target.text.trim_end(), next.text.trim_start());
+            target.words.extend(next.words);
+        }
+        TranscriptEdit::SetTiming { segment, start, end } => {
+            if *segment >= count {
+                return Err(segment_error(*segment, count));
+            }
+            if start.as_secs() < 0.0 || end <= start {
+                return Err(AppError::TranscriptError("A segment must end after it starts".to_string()));
+            }
+            if *segment > 0 && *start < transcript.segments[*segment - 1].end {
+                return Err(AppError::TranscriptError("Segment would overlap the previous one".to_string()));
+            }
+            if *segment + 1 < count && *end > transcript.segments[*segment + 1].start {
+                return Err(AppError::TranscriptError("Segment would overlap the next one".to_string()));
+            }
+            retime(&mut transcript.segments[*segment], *start, *end);
+        }
+    }
+    transcript.rebuild_text();
+    Ok(())
+}
+
+/// Segment text in Whisper's style, with a leading space
+fn segment_text(words: &[&str]) -> String {
+    format!(" {}", words.join(" "))
+}
+
+/// Replace the text, keeping timings for the unchanged words at either end
+///
+/// The changed words in the middle share the time of the words they replaced.
+fn edit_text(segment: &mut TranscriptSegment, text: &str) {
+    let new_words: Vec<&str> = text.split_whitespace().collect();
+    segment.text = segment_text(&new_words);
+    if segment.words.is_empty() {
+        return;
+    }
+
+    let old = std::mem::take(&mut segment.words);
+    let prefix = old.iter().zip(&new_words).take_while(|(o, n)| o.text == **n).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new_words[prefix..].iter().rev())
+        .take_while(|(o, n)| o.text == **n)
+        .count();
+
+    let replaced = &old[prefix..old.len() - suffix];
+    let inserted = &new_words[prefix..new_words.len() - suffix];
+
+    // The span the replaced words covered, or the gap between their neighbours
+    let span_start = replaced
+        .first()
+        .map(|w| w.start)
+        .or_else(|| prefix.checked_sub(1).map(|i| old[i].end))
+        .unwrap_or(segment.start);
+    let span_end = replaced
+        .last()
+        .map(|w| w.end)
+        .or_else(|| old.get(prefix).map(|w| w.start))
+        .unwrap_or(span_start);
+
+    let mut words: Vec<TranscriptWord> = old[..prefix].to_vec();
+    let step = (span_end - span_start).as_secs() / inserted.len().max(1) as f64;
+    for (i, text) in inserted.iter().enumerate() {
+        words.push(TranscriptWord {
+            text: text.to_string(),
+            start: span_start + Timestamp::from_secs(step * i as f64),
+            end: span_start + Timestamp::from_secs(step * (i + 1) as f64),
+            // Typed by a person, so fully trusted
+            probability: 1.0,
+        });
+    }
+    words.extend_from_slice(&old[old.len() - suffix..]);
+    segment.words = words;
+}
+
+/// Split before the `word`-th word, timing the cut from word timestamps or by text length
+fn split_segment(segment: &TranscriptSegment, word: usize) -> Result<(TranscriptSegment, TranscriptSegment), AppError> {
+    let texts: Vec<&str> = if segment.words.is_empty() {
+        segment.text.split_whitespace().collect()
+    } else {
+        segment.words.iter().map(|w| w.text.as_str()).collect()
+    };
+    if word == 0 || word >= texts.len() {
+        return Err(AppError::TranscriptError(format!(
+            "Cannot split before word {} of a {}-word segment",
+            word,
+            texts.len()
+        )));
+    }
+
+    let (at, left_end) = if segment.words.is_empty() {
+        let chars = |words: &[&str]| words.iter().map(|w| w.chars().count()).sum::<usize>() as f64;
+        let fraction = chars(&texts[..word]) / chars(&texts).max(1.0);
+        let at = segment.start + Timestamp::from_secs((segment.end - segment.start).as_secs() * fraction);
+        (at, at)
+    } else {
+        (segment.words[word].start, segment.words[word - 1].end)
+    };
+
+    let left = TranscriptSegment {
+        start: segment.start,
+        end: left_end,
+        text: segment_text(&texts[..word]),
+        words: segment.words.iter().take(word).cloned().collect(),
+    };
+    let right = TranscriptSegment {
+        start: at,
+        end: segment.end,
+        text: segment_text(&texts[word..]),
+        words: segment.words.iter().skip(word).cloned().collect(),
+    };
+    Ok((left, right))
+}
+
+/// Move a segment to `start..end`, mapping word timings linearly onto the new span
+fn retime(segment: &mut TranscriptSegment, start: Timestamp, end: Timestamp) {
+    let old_start = segment.start;
+    let old_len = (segment.end - segment.start).as_secs();
+    let scale = if old_len > 0.0 { (end - start).as_secs() / old_len } else { 0.0 };
+    let map = |t: Timestamp| start + Timestamp::from_secs((t - old_start).as_secs() * scale);
+
+    for word in &mut segment.words {
+        word.start = map(word.start);
+        word.end = map(word.end);
+    }
+    segment.start = start;
+    segment.end = end;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(t: Timestamp) -> f64 {
+        (t.as_secs() * 1000.0).round() / 1000.0
+    }
+
+    /// Two segments whose words last 0.5s each
+    fn transcript() -> Transcript {
+        let segment = |start: f64, words: &[&str]| {
+            let words: Vec<TranscriptWord> = words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| TranscriptWord {
+                    text: w.to_string(),
+                    start: Timestamp::from_secs(start + i as f64 * 0.5),
+                    end: Timestamp::from_secs(start + i as f64 * 0.5 + 0.5),
+                    probability: 0.8,
+                })
+                .collect();
+            TranscriptSegment {
+                start: Timestamp::from_secs(start),
+                end: words.last().unwrap().end,
+                text: segment_text(&words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>()),
+                words,
+            }
+        };
+        let mut transcript = Transcript {
+            segments: vec![segment(0.0, &["The", "quick", "brown", "fox."]), segment(3.0, &["It", "jumped."])],
+            text: String::new(),
+            language: None,
+            language_probability: None,
+            translated: false,
+        };
+        transcript.rebuild_text();
+        transcript
+    }
+
+    #[test]
+    fn edit_text_keeps_unchanged_word_timings() {
+        let mut t = transcript();
+        apply_edit(
+            &mut t,
+            &TranscriptEdit::EditText {
+                segment: 0,
+                text: "The quick red little fox.".to_string(),
+            },
+        )
+        .unwrap();
+
+        let segment = &t.segments[0];
+        assert_eq!(segment.text, " The quick red little fox.");
+        let words: Vec<(&str, f64, f64)> =
+            segment.words.iter().map(|w| (w.text.as_str(), secs(w.start), secs(w.end))).collect();
+        assert_eq!(
+            words,
+            [("The", 0.0, 0.5), ("quick", 0.5, 1.0), ("red", 1.0, 1.25), ("little", 1.25, 1.5), ("fox.", 1.5, 2.0)]
+        );
+        assert_eq!(t.text, " The quick red little fox.  It jumped. ");
+    }
+
+    #[test]
+    fn split_and_merge_segments() {
+        let mut t = transcript();
+        apply_edit(&mut t, &TranscriptEdit::Split { segment: 0, word: 2 }).unwrap();
+        assert_eq!(t.segments.len(), 3);
+        assert_eq!(t.segments[0].text, " The quick");
+        assert_eq!((secs(t.segments[0].end), secs(t.segments[1].start)), (1.0, 1.0));
+        assert_eq!(t.segments[1].words.len(), 2);
+
+        apply_edit(&mut t, &TranscriptEdit::Merge { segment: 1 }).unwrap();
+        assert_eq!(t.segments[1].text, " brown fox. It jumped.");
+        assert_eq!(secs(t.segments[1].end), 4.0);
+
+        assert!(apply_edit(&mut t, &TranscriptEdit::Split { segment: 0, word: 0 }).is_err());
+        assert!(apply_edit(&mut t, &TranscriptEdit::Merge { segment: 1 }).is_err());
+    }
+
+    #[test]
+    fn set_timing_scales_words_and_rejects_overlaps() {
+        let mut t = transcript();
+        let set = |segment, start, end| TranscriptEdit::SetTiming {
+            segment,
+            start: Timestamp::from_secs(start),
+            end: Timestamp::from_secs(end),
+        };
+
+        apply_edit(&mut t, &set(1, 2.5, 4.5)).unwrap();
+        let words: Vec<(f64, f64)> = t.segments[1].words.iter().map(|w| (secs(w.start), secs(w.end))).collect();
+        assert_eq!(words, [(2.5, 3.5), (3.5, 4.5)]);
+
+        assert!(apply_edit(&mut t, &set(1, 1.5, 4.0)).is_err());
+        assert!(apply_edit(&mut t, &set(0, 0.0, 3.0)).is_err());
+        assert!(apply_edit(&mut t, &set(0, 1.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn history_undoes_redoes_and_restores() {
+        let mut history = EditHistory::new(transcript());
+        history.apply(&TranscriptEdit::Merge { segment: 0 }).unwrap();
+        history
+            .apply(&TranscriptEdit::EditText {
+                segment: 0,
+                text: "Hello".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(history.undo().unwrap().segments[0].text, " The quick brown fox. It jumped.");
+        assert_eq!(history.undo().unwrap().segments.len(), 2);
+        assert!(history.undo().is_err());
+        assert_eq!(history.redo().unwrap().segments.len(), 1);
+
+        // Editing after an undo drops the redo branch
+        history.apply(&TranscriptEdit::Split { segment: 0, word: 1 }).unwrap();
+        assert!(history.redo().is_err());
+
+        assert_eq!(history.restore(0).unwrap().segments[1].text, " It jumped.");
+        let versions = history.versions();
+        assert_eq!(versions.len(), 4);
+        assert!(versions[3].current);
+        assert_eq!(versions[3].description, "Restored version 0");
+    }
+
+    #[test]
+    fn version_ids_survive_dropping_old_versions() {
+        let mut history = EditHistory::new(transcript());
+        for i in 0..MAX_VERSIONS + 5 {
+            history
+                .apply(&TranscriptEdit::EditText {
+                    segment: 1,
+                    text: format!("Take {}", i),
+                })
+                .unwrap();
+        }
+        let versions = history.versions();
+        assert_eq!(versions.len(), MAX_VERSIONS);
+        assert_eq!(versions[0].id, 6);
+
+        assert_eq!(history.restore(6).unwrap().segments[1].text, " Take 5");
+        assert!(history.restore(3).is_err());
+    }
+
+    #[test]
+    fn rollback_brings_back_the_redo_branch() {
+        let mut history = EditHistory::new(transcript());
+        history.apply(&TranscriptEdit::Merge { segment: 0 }).unwrap();
+        history.undo().unwrap();
+
+        let checkpoint = history.checkpoint();
+        history.apply(&TranscriptEdit::Split { segment: 0, word: 1 }).unwrap();
+        history.rollback(checkpoint);
+
+        assert_eq!(history.current().segments.len(), 2);
+        assert_eq!(history.redo().unwrap().segments.len(), 1);
+        assert_eq!(history.versions().len(), 2);
+    }
+
+    #[test]
+    fn editor_saves_history_and_keeps_it_when_saving_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("talk.json").to_string_lossy().into_owned();
+        save_transcript(&path, &transcript()).unwrap();
+
+        let editor = TranscriptEditor::default();
+        // Listing versions opens the transcript just like editing does
+        assert_eq!(editor.versions(&path).unwrap().len(), 1);
+        editor.update(&path, |history| history.apply(&TranscriptEdit::Merge { segment: 0 })).unwrap();
+
+        // A new session picks up where the last one left off
+        let editor = TranscriptEditor::default();
+        assert_eq!(editor.open(&path).unwrap().segments.len(), 1);
+        assert_eq!(editor.versions(&path).unwrap().len(), 2);
+
+        // With the transcript path taken by a directory, saving fails and nothing changes
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        assert!(editor.update(&path, |history| history.undo()).is_err());
+        let versions = editor.versions(&path).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(versions[1].current);
+    }
+
+    #[test]
+    fn history_file_grows_by_one_small_entry_per_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("talk.json").to_string_lossy().into_owned();
+        save_transcript(&path, &transcript()).unwrap();
+        let lines = || fs::read_to_string(history_path(&path)).unwrap().lines().map(String::from).collect::<Vec<_>>();
+
+        let editor = TranscriptEditor::default();
+        editor.open(&path).unwrap();
+        let opened = lines();
+        editor.update(&path, |history| history.apply(&TranscriptEdit::Merge { segment: 0 })).unwrap();
+        editor.update(&path, |history| history.undo()).unwrap();
+
+        // Earlier lines are left alone and the new ones hold no transcript
+        let after = lines();
+        assert_eq!(after[..opened.len()], opened[..]);
+        assert_eq!(after.len(), opened.len() + 2);
+        assert!(after[opened.len()..].iter().all(|line| !line.contains("\"transcript\"")));
+
+        let editor = TranscriptEditor::default();
+        assert_eq!(editor.open(&path).unwrap().segments.len(), 2);
+        assert_eq!(editor.update(&path, |history| history.redo()).unwrap().segments.len(), 1);
+    }
+
+    #[test]
+    fn snapshots_are_kept_every_few_edits() {
+        let mut history = EditHistory::new(transcript());
+        for i in 0..SNAPSHOT_EVERY * 2 {
+            history
+                .apply(&TranscriptEdit::EditText {
+                    segment: 1,
+                    text: format!("Take {}", i),
+                })
+                .unwrap();
+        }
+        let snapshots = history
+            .versions
+            .iter()
+            .filter(|version| matches!(version.change, Change::Snapshot { .. }))
+            .count();
+        assert_eq!(snapshots, 3);
+
+        history.undo().unwrap();
+        assert_eq!(history.current().segments[1].text, format!(" Take {}", SNAPSHOT_EVERY * 2 - 2));
+        assert_eq!(history.transcript_at(5).unwrap().segments[1].text, " Take 4");
+    }
+}
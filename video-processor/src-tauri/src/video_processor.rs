@@ -1,12 +1,16 @@
 use crate::error::AppError;
+use crate::jobs::CancelToken;
+use crate::progress::{parse_ffmpeg_out_time, ProgressReporter};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use std::process::Command;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
 use tempfile::NamedTempFile;
-use std::fs;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VideoProcessingOptions {
     pub output_path: String,
     pub cut_points: Vec<CutPoint>,
@@ -14,6 +18,7 @@ pub struct VideoProcessingOptions {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CutPoint {
     pub start_time: f64,
     pub end_time: f64,
@@ -21,7 +26,7 @@ pub struct CutPoint {
 }
 
 /// Extracts audio from a video file using FFmpeg
-pub fn extract_audio(video_path: &str) -> Result<String, AppError> {
+pub fn extract_audio(video_path: &str, progress: &ProgressReporter, cancel: &CancelToken) -> Result<String, AppError> {
     let video_path = Path::new(video_path);
     let file_stem = video_path.file_stem()
         .ok_or_else(|| AppError::VideoProcessingError("Invalid video file path".to_string()))?;
@@ -31,107 +36,246 @@ pub fn extract_audio(video_path: &str) -> Result<String, AppError> {
     
     let audio_path = output_dir.join(format!("{}_audio.wav", file_stem.to_string_lossy()));
     
+    let duration = probe_duration(video_path).ok();
+
     // Use FFmpeg to extract audio
-    let status = Command::new("ffmpeg")
-        .arg("-i")
-        .arg(video_path)
-        .arg("-vn") // Disable video
-        .arg("-acodec")
-        .arg("pcm_s16le") // Use PCM 16-bit format for Whisper
-        .arg("-ar")
-        .arg("16000") // 16kHz sample rate for Whisper
-        .arg("-ac")
-        .arg("1") // Mono audio
-        .arg("-y") // Overwrite output file if it exists
-        .arg(&audio_path)
-        .status()
-        .map_err(|e| AppError::FFmpegError(format!("Failed to run FFmpeg: {}", e)))?;
-    
-    if !status.success() {
-        return Err(AppError::FFmpegError("FFmpeg command failed".to_string()));
+    let args: Vec<OsString> = vec![
+        "-i".into(),
+        file_url(video_path),
+        "-vn".into(), // Disable video
+        "-acodec".into(),
+        "pcm_s16le".into(), // Use PCM 16-bit format for Whisper
+        "-ar".into(),
+        "16000".into(), // 16kHz sample rate for Whisper
+        "-ac".into(),
+        "1".into(), // Mono audio
+        "-y".into(), // Overwrite output file if it exists
+        file_url(&audio_path),
+    ];
+    if let Err(e) = run_ffmpeg(&args, duration, progress, cancel) {
+        remove_partial_output(&audio_path, &e);
+        return Err(e);
     }
     
     Ok(audio_path.to_string_lossy().to_string())
 }
 
+/// Everything FFmpeg needs for a render, kept as structured data and only ever
+/// handed to the child process as separate arguments
+#[derive(Debug)]
+pub struct RenderJob {
+    pub input_path: PathBuf,
+    pub output_path: PathBuf,
+    pub filtergraph_path: PathBuf,
+    pub has_audio: bool,
+}
+
+impl RenderJob {
+    /// Build the FFmpeg argument vector for this job
+    pub fn ffmpeg_args(&self) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            "-y".into(), // Overwrite output file if it exists
+            "-i".into(),
+            file_url(&self.input_path),
+            "-filter_complex_script".into(),
+            self.filtergraph_path.clone().into_os_string(),
+            "-map".into(),
+            "[outv]".into(),
+        ];
+        if self.has_audio {
+            args.extend(["-map".into(), "[outa]".into(), "-c:a".into(), "aac".into()]);
+        }
+        args.extend([
+            "-c:v".into(),
+            "libx264".into(),
+            "-pix_fmt".into(),
+            "yuv420p".into(),
+            file_url(&self.output_path),
+        ]);
+        args
+    }
+}
+
+/// Prefix a path with FFmpeg's `file:` protocol so names that start with `-` or
+/// contain `:` are never read as options or other protocols
+fn file_url(path: &Path) -> OsString {
+    let mut url = OsString::from("file:");
+    url.push(path.as_os_str());
+    url
+}
+
 /// Process video based on transcript and cut points
 pub fn process_video(
     video_path: &str,
     _transcript_path: &str,
     options: VideoProcessingOptions,
+    progress: &ProgressReporter,
+    cancel: &CancelToken,
 ) -> Result<String, AppError> {
-    // Create a Python script to process the video with MoviePy
-    let python_script = create_moviepy_script(video_path, &options)?;
-    let script_file = NamedTempFile::new()
-        .map_err(|e| AppError::IoError(e))?;
-    let script_path = script_file.path().to_string_lossy().to_string();
-    
-    // Write the Python script to a temporary file
-    fs::write(&script_path, python_script)
-        .map_err(|e| AppError::IoError(e))?;
-    
-    // Run the Python script
-    let status = Command::new("python")
-        .arg(&script_path)
-        .status()
-        .map_err(|e| AppError::VideoProcessingError(format!("Failed to run Python script: {}", e)))?;
-    
-    if !status.success() {
-        return Err(AppError::VideoProcessingError("Python script failed".to_string()));
+    let has_audio = has_audio_stream(Path::new(video_path))?;
+    let filtergraph = build_filtergraph(&options.cut_points, options.apply_zoom_effects, has_audio)?;
+
+    // Write the filtergraph to a temporary file so long cut lists don't hit command line limits
+    let mut script_file = NamedTempFile::new()?;
+    script_file.write_all(filtergraph.as_bytes())?;
+
+    let job = RenderJob {
+        input_path: PathBuf::from(video_path),
+        output_path: PathBuf::from(&options.output_path),
+        filtergraph_path: script_file.path().to_path_buf(),
+        has_audio,
+    };
+
+    let total_duration: f64 = options.cut_points
+        .iter()
+        .map(|cut| cut.end_time - cut.start_time)
+        .sum();
+    if let Err(e) = run_ffmpeg(&job.ffmpeg_args(), Some(total_duration), progress, cancel) {
+        remove_partial_output(&job.output_path, &e);
+        return Err(e);
     }
-    
+
     Ok(options.output_path)
 }
 
-/// Create a Python script for MoviePy to process the video
-fn create_moviepy_script(video_path: &str, options: &VideoProcessingOptions) -> Result<String, AppError> {
-    let mut script = String::from(r#"
-import sys
-from moviepy.editor import VideoFileClip, concatenate_videoclips
+/// Delete whatever FFmpeg managed to write before a job was cancelled
+fn remove_partial_output(path: &Path, error: &AppError) {
+    if matches!(error, AppError::Cancelled) && path.exists() {
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("Failed to remove partial output {}: {}", path.display(), e);
+        }
+    }
+}
 
-def process_video():
-    # Load the video file
-    video = VideoFileClip("VIDEO_PATH")
-    
-    # Define cut points
-    cut_points = CUT_POINTS
-    
-    # Create subclips
-    clips = []
-    for cut in cut_points:
-        start_time = cut["start_time"]
-        end_time = cut["end_time"]
-        clip = video.subclip(start_time, end_time)
-        
-        # Apply zoom effects if enabled
-        if APPLY_ZOOM_EFFECTS:
-            # This is a simple zoom effect, can be customized
-            clip = clip.fx(lambda c: c.resize(1.1))
-        
-        clips.append(clip)
-    
-    # Concatenate clips
-    final_clip = concatenate_videoclips(clips)
-    
-    # Write the result
-    final_clip.write_videofile("OUTPUT_PATH", codec="libx264")
-    
-    # Close clips
-    video.close()
-    final_clip.close()
+/// Run FFmpeg with the given arguments, reporting progress from its `-progress` output
+fn run_ffmpeg(
+    args: &[OsString],
+    total_duration: Option<f64>,
+    progress: &ProgressReporter,
+    cancel: &CancelToken,
+) -> Result<(), AppError> {
+    cancel.check()?;
+    progress.report(0.0);
 
-if __name__ == "__main__":
-    process_video()
-"#);
-    
-    // Replace placeholders with actual values
-    script = script.replace("VIDEO_PATH", video_path);
-    script = script.replace("OUTPUT_PATH", &options.output_path);
-    script = script.replace("CUT_POINTS", &serde_json::to_string(&options.cut_points)
-        .map_err(|e| AppError::VideoProcessingError(format!("Failed to serialize cut points: {}", e)))?);
-    script = script.replace("APPLY_ZOOM_EFFECTS", &options.apply_zoom_effects.to_string());
-    
-    Ok(script)
+    let mut child = Command::new("ffmpeg")
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats")
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::FFmpegError(format!("Failed to run FFmpeg: {}", e)))?;
+
+    let stdout = child.stdout.take();
+    cancel.attach_child(child)?;
+
+    if let Some(stdout) = stdout {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let (Some(out_time), Some(total)) = (parse_ffmpeg_out_time(&line), total_duration) {
+                if total > 0.0 {
+                    progress.report(out_time / total);
+                }
+            }
+        }
+    }
+
+    let status = cancel.wait_child()?;
+
+    if !status.success() {
+        return Err(AppError::FFmpegError("FFmpeg command failed".to_string()));
+    }
+
+    progress.report(1.0);
+    Ok(())
+}
+
+/// Get the duration of a media file in seconds using ffprobe
+fn probe_duration(path: &Path) -> Result<f64, AppError> {
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("format=duration")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(file_url(path))
+        .output()
+        .map_err(|e| AppError::FFmpegError(format!("Failed to run ffprobe: {}", e)))?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| AppError::FFmpegError("ffprobe did not report a duration".to_string()))
+}
+
+/// Build an FFmpeg filtergraph that trims each cut point and concatenates the results
+fn build_filtergraph(cut_points: &[CutPoint], apply_zoom_effects: bool, has_audio: bool) -> Result<String, AppError> {
+    if cut_points.is_empty() {
+        return Err(AppError::VideoProcessingError("No cut points to render".to_string()));
+    }
+
+    let mut filters = Vec::new();
+    let mut concat_inputs = String::new();
+
+    for (i, cut) in cut_points.iter().enumerate() {
+        if !(cut.start_time >= 0.0 && cut.end_time > cut.start_time) {
+            return Err(AppError::VideoProcessingError(format!(
+                "Invalid cut point {}: {} to {}",
+                i + 1,
+                cut.start_time,
+                cut.end_time
+            )));
+        }
+
+        let mut video = format!(
+            "[0:v]trim=start={:.3}:end={:.3},setpts=PTS-STARTPTS",
+            cut.start_time, cut.end_time
+        );
+        if apply_zoom_effects {
+            // Crop the centre of the frame and scale it back up to roughly the original size
+            video.push_str(",crop=trunc(iw/1.1/2)*2:trunc(ih/1.1/2)*2,scale=trunc(iw*1.1/2)*2:trunc(ih*1.1/2)*2,setsar=1");
+        }
+        filters.push(format!("{}[v{}]", video, i));
+        concat_inputs.push_str(&format!("[v{}]", i));
+
+        if has_audio {
+            filters.push(format!(
+                "[0:a]atrim=start={:.3}:end={:.3},asetpts=PTS-STARTPTS[a{}]",
+                cut.start_time, cut.end_time, i
+            ));
+            concat_inputs.push_str(&format!("[a{}]", i));
+        }
+    }
+
+    if has_audio {
+        filters.push(format!("{}concat=n={}:v=1:a=1[outv][outa]", concat_inputs, cut_points.len()));
+    } else {
+        filters.push(format!("{}concat=n={}:v=1:a=0[outv]", concat_inputs, cut_points.len()));
+    }
+
+    Ok(filters.join(";\n"))
+}
+
+/// Check whether the input file has at least one audio stream
+fn has_audio_stream(video_path: &Path) -> Result<bool, AppError> {
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("a")
+        .arg("-show_entries")
+        .arg("stream=index")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(file_url(video_path))
+        .output()
+        .map_err(|e| AppError::FFmpegError(format!("Failed to run ffprobe: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::FFmpegError("ffprobe could not read the input file".to_string()));
+    }
+
+    Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
 }
 
 /// Check if FFmpeg is installed
@@ -146,15 +290,83 @@ pub async fn check_ffmpeg_installed() -> bool {
     }
 }
 
-/// Check if Python and MoviePy are installed
-pub async fn check_moviepy_installed() -> bool {
-    let command = Command::new("python")
-        .arg("-c")
-        .arg("import moviepy")
-        .output();
-    
-    match command {
-        Ok(output) => output.status.success(),
-        Err(_) => false,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AWKWARD_NAMES: &[&str] = &[
+        "it's a \"quoted\" clip.mp4",
+        "back\\slash.mp4",
+        "vidéo d'entretien 日本語.mp4",
+        "with spaces  and\ttabs.mp4",
+        "line one\nline two.mp4",
+        "OUTPUT_PATH.mp4",
+        "-starts-with-dash.mp4",
+        "concat:a.mp4|b.mp4",
+    ];
+
+    fn cut(start_time: f64, end_time: f64) -> CutPoint {
+        CutPoint {
+            start_time,
+            end_time,
+            description: String::new(),
+        }
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn render_args_pass_paths_verbatim() {
+        for name in AWKWARD_NAMES {
+            let dir = Path::new("/tmp/some dir");
+            let job = RenderJob {
+                input_path: dir.join(name),
+                output_path: dir.join(format!("out {}", name)),
+                filtergraph_path: dir.join("graph.txt"),
+                has_audio: true,
+            };
+            let args = job.ffmpeg_args();
+
+            let mut expected_input = OsString::from("file:");
+            expected_input.push(dir.join(name));
+            let mut expected_output = OsString::from("file:");
+            expected_output.push(dir.join(format!("out {}", name)));
+
+            assert_eq!(args.iter().filter(|a| **a == expected_input).count(), 1, "{}", name);
+            assert_eq!(args.last(), Some(&expected_output), "{}", name);
+            let input_index = args.iter().position(|a| *a == expected_input).unwrap();
+            assert_eq!(args[input_index - 1], "-i");
+        }
+    }
+
+    #[test]
+    fn render_args_without_audio_skip_audio_map() {
+        let job = RenderJob {
+            input_path: PathBuf::from("in.mp4"),
+            output_path: PathBuf::from("out.mp4"),
+            filtergraph_path: PathBuf::from("graph.txt"),
+            has_audio: false,
+        };
+        let args = job.ffmpeg_args();
+        assert!(!args.iter().any(|a| a == "[outa]"));
+        assert!(args.iter().any(|a| a == "[outv]"));
+    }
+
+    #[test]
+    fn filtergraph_trims_and_concats_each_cut() {
+        let graph = build_filtergraph(&[cut(0.0, 1.5), cut(3.25, 7.0)], false, true).unwrap();
+        assert_eq!(
+            graph,
+            "[0:v]trim=start=0.000:end=1.500,setpts=PTS-STARTPTS[v0];\n\
+             [0:a]atrim=start=0.000:end=1.500,asetpts=PTS-STARTPTS[a0];\n\
+             [0:v]trim=start=3.250:end=7.000,setpts=PTS-STARTPTS[v1];\n\
+             [0:a]atrim=start=3.250:end=7.000,asetpts=PTS-STARTPTS[a1];\n\
+             [v0][a0][v1][a1]concat=n=2:v=1:a=1[outv][outa]"
+        );
+    }
+
+    #[test]
+    fn filtergraph_rejects_invalid_cuts() {
+        assert!(build_filtergraph(&[], false, true).is_err());
+        assert!(build_filtergraph(&[cut(2.0, 1.0)], false, true).is_err());
+        assert!(build_filtergraph(&[cut(f64::NAN, 1.0)], false, true).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::error::AppError;
+use crate::whisper::{Transcript, TranscriptWord};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+/// A project's names, terms and text fixes for transcription
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Vocabulary {
+    /// Product, speaker and other names, spelled the way they should appear
+    ///
+    /// They are given to Whisper as prompt, and any differently cased match in
+    /// the output is rewritten to this spelling.
+    pub terms: Vec<String>,
+    /// Find/replace rules applied in order after inference
+    pub rules: Vec<ReplacementRule>,
+}
+
+/// One find/replace rule for transcript text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReplacementRule {
+    /// Replace a phrase matched as whole words
+    #[serde(rename_all = "camelCase")]
+    Literal {
+        find: String,
+        replace: String,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// Replace matches of a regular expression, expanding `$1` and `$name` groups
+    Regex { pattern: String, replace: String },
+}
+
+impl Vocabulary {
+    /// Combine a user prompt with the vocabulary terms into Whisper's initial prompt
+    pub fn prompt(&self, initial_prompt: Option<&str>) -> Option<String> {
+        let terms: Vec<&str> = self.terms.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
+        let initial_prompt = initial_prompt.map(str::trim).filter(|p| !p.is_empty());
+
+        match (initial_prompt, terms.is_empty()) {
+            (None, true) => None,
+            (Some(prompt), true) => Some(prompt.to_string()),
+            (None, false) => Some(format!("{}.", terms.join(", "))),
+            (Some(prompt), false) => Some(format!("{} {}.", prompt, terms.join(", "))),
+        }
+    }
+
+    /// Compile the rules and casing fixes, reporting the first invalid pattern
+    pub fn compile(&self) -> Result<TranscriptRules, AppError> {
+        let mut rules = Vec::new();
+
+        for rule in &self.rules {
+            let compiled = match rule {
+                ReplacementRule::Literal { find, replace, case_sensitive } => {
+                    if find.trim().is_empty() {
+                        continue;
+                    }
+                    CompiledRule {
+                        regex: build_regex(&phrase_pattern(find, !case_sensitive))?,
+                        replace: replace.clone(),
+                        expand: false,
+                    }
+                }
+                ReplacementRule::Regex { pattern, replace } => CompiledRule {
+                    regex: build_regex(pattern)?,
+                    replace: replace.clone(),
+                    expand: true,
+                },
+            };
+            rules.push(compiled);
+        }
+
+        // Casing fixes run last so they also tidy up what the rules produced
+        for term in self.terms.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            rules.push(CompiledRule {
+                regex: build_regex(&phrase_pattern(term, true))?,
+                replace: term.to_string(),
+                expand: false,
+            });
+        }
+
+        Ok(TranscriptRules { rules })
+    }
+}
+
+fn build_regex(pattern: &str) -> Result<Regex, AppError> {
+    Regex::new(pattern).map_err(|e| AppError::Other(format!("Invalid replacement pattern {}: {}", pattern, e)))
+}
+
+/// Pattern matching `phrase` as whole words, with any run of whitespace between its words
+fn phrase_pattern(phrase: &str, ignore_case: bool) -> String {
+    let phrase = phrase.trim();
+    let body = phrase.split_whitespace().map(regex::escape).collect::<Vec<_>>().join(r"\s+");
+
+    // `\b` only works next to word characters, so terms like "C++" skip it on that side
+    let is_word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let start = if is_word_char(phrase.chars().next()) { r"\b" } else { "" };
+    let end = if is_word_char(phrase.chars().last()) { r"\b" } else { "" };
+
+    format!("{}{}{}{}", if ignore_case { "(?i)" } else { "" }, start, body, end)
+}
+
+struct CompiledRule {
+    regex: Regex,
+    replace: String,
+    expand: bool,
+}
+
+impl CompiledRule {
+    fn replacement(&self, caps: &Captures) -> String {
+        if self.expand {
+            let mut out = String::new();
+            caps.expand(&self.replace, &mut out);
+            out
+        } else {
+            self.replace.clone()
+        }
+    }
+}
+
+/// Compiled vocabulary rules, ready to run over transcripts
+pub struct TranscriptRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl TranscriptRules {
+    /// Rewrite segment and word text in place
+    ///
+    /// Word timings are kept: a match spanning several words collapses them into
+    /// one word covering their combined time, and words a rule empties are dropped.
+    pub fn apply(&self, transcript: &mut Transcript) {
+        if self.rules.is_empty() {
+            return;
+        }
+
+        for segment in &mut transcript.segments {
+            for rule in &self.rules {
+                segment.text = rule
+                    .regex
+                    .replace_all(&segment.text, |caps: &Captures| rule.replacement(caps))
+                    .into_owned();
+                apply_to_words(rule, &mut segment.words);
+            }
+        }
+
+        transcript.rebuild_text();
+    }
+}
+
+/// Run one rule over the words of a segment as if they were joined by single spaces
+fn apply_to_words(rule: &CompiledRule, words: &mut Vec<TranscriptWord>) {
+    if words.is_empty() {
+        return;
+    }
+
+    let joined = words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
+    let mut offsets = Vec::with_capacity(words.len());
+    let mut offset = 0;
+    for word in words.iter() {
+        offsets.push(offset);
+        offset += word.text.len() + 1;
+    }
+    let word_at = |pos: usize| offsets.iter().rposition(|&o| o <= pos).unwrap_or(0);
+
+    let matches: Vec<(usize, usize, String)> = rule
+        .regex
+        .captures_iter(&joined)
+        .filter_map(|caps| {
+            let m = caps.get(0)?;
+            (!m.is_empty()).then(|| (m.start(), m.end(), rule.replacement(&caps)))
+        })
+        .collect();
+
+    // Work backwards so earlier offsets stay valid
+    for (start, end, replacement) in matches.into_iter().rev() {
+        let first = word_at(start);
+        let last = word_at(end - 1).max(first);
+
+        let first_word = &words[first].text;
+        let prefix = &first_word[..(start - offsets[first]).min(first_word.len())];
+        let last_word = &words[last].text;
+        let suffix = &last_word[(end - offsets[last]).min(last_word.len())..];
+        let text = format!("{}{}{}", prefix, replacement, suffix).trim().to_string();
+
+        let end_time = words[last].end;
+        let probability = words[first..=last].iter().map(|w| w.probability).fold(f32::INFINITY, f32::min);
+        words.drain(first + 1..=last);
+
+        if text.is_empty() {
+            words.remove(first);
+        } else {
+            let word = &mut words[first];
+            word.text = text;
+            word.end = end_time;
+            word.probability = probability;
+        }
+    }
+}
+
+/// Apply a vocabulary's rules to a saved transcript, rewriting the file
+pub fn apply_vocabulary_to_file(transcript_path: &str, vocabulary: &Vocabulary) -> Result<(), AppError> {
+    let rules = vocabulary.compile()?;
+    let mut transcript = crate::whisper::load_transcript(transcript_path)?;
+    rules.apply(&mut transcript);
+    crate::whisper::save_transcript(transcript_path, &transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::Timestamp;
+    use crate::whisper::TranscriptSegment;
+
+    fn word(text: &str, start: f64, end: f64) -> TranscriptWord {
+        TranscriptWord {
+            text: text.to_string(),
+            start: Timestamp::from_secs(start),
+            end: Timestamp::from_secs(end),
+            probability: 0.9,
+        }
+    }
+
+    fn transcript(words: Vec<TranscriptWord>) -> Transcript {
+        let text = format!(" {}", words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "));
+        let segment = TranscriptSegment {
+            start: Timestamp::ZERO,
+            end: Timestamp::from_secs(5.0),
+            text,
+            words,
+        };
+        Transcript {
+            segments: vec![segment],
+            text: String::new(),
+            language: None,
+            language_probability: None,
+            translated: false,
+        }
+    }
+
+    #[test]
+    fn terms_fix_casing_and_merge_split_words() {
+        let vocabulary = Vocabulary {
+            terms: vec!["GitHub".to_string(), "Jane Doe".to_string()],
+            rules: vec![ReplacementRule::Literal {
+                find: "git hub".to_string(),
+                replace: "github".to_string(),
+                case_sensitive: false,
+            }],
+        };
+        let mut t = transcript(vec![
+            word("Ask", 0.0, 0.3),
+            word("jane", 0.3, 0.6),
+            word("doe,", 0.6, 0.9),
+            word("she", 1.0, 1.2),
+            word("uses", 1.2, 1.5),
+            word("Git", 1.5, 1.8),
+            word("hub.", 1.8, 2.2),
+        ]);
+
+        vocabulary.compile().unwrap().apply(&mut t);
+
+        let segment = &t.segments[0];
+        assert_eq!(segment.text, " Ask Jane Doe, she uses GitHub.");
+        assert_eq!(t.text, " Ask Jane Doe, she uses GitHub. ");
+        let words: Vec<&str> = segment.words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(words, ["Ask", "Jane Doe,", "she", "uses", "GitHub."]);
+        assert_eq!((segment.words[4].start.as_secs(), segment.words[4].end.as_secs()), (1.5, 2.2));
+    }
+
+    #[test]
+    fn regex_rules_expand_groups_and_drop_emptied_words() {
+        let vocabulary = Vocabulary {
+            terms: Vec::new(),
+            rules: vec![
+                ReplacementRule::Regex {
+                    pattern: r"(?i)\s*\bum\b,?".to_string(),
+                    replace: String::new(),
+                },
+                ReplacementRule::Regex {
+                    pattern: r"(\d+) percent".to_string(),
+                    replace: "$1%".to_string(),
+                },
+            ],
+        };
+        let mut t = transcript(vec![
+            word("Um,", 0.0, 0.2),
+            word("about", 0.3, 0.6),
+            word("20", 0.6, 0.8),
+            word("percent", 0.8, 1.2),
+        ]);
+
+        vocabulary.compile().unwrap().apply(&mut t);
+
+        let segment = &t.segments[0];
+        assert_eq!(segment.text, " about 20%");
+        let words: Vec<&str> = segment.words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(words, ["about", "20%"]);
+        assert_eq!(segment.words[1].end.as_secs(), 1.2);
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported() {
+        let vocabulary = Vocabulary {
+            terms: Vec::new(),
+            rules: vec![ReplacementRule::Regex {
+                pattern: "(unclosed".to_string(),
+                replace: String::new(),
+            }],
+        };
+        assert!(matches!(vocabulary.compile(), Err(AppError::Other(_))));
+    }
+
+    #[test]
+    fn prompt_lists_terms_after_user_prompt() {
+        let vocabulary = Vocabulary {
+            terms: vec!["Acme".to_string(), " ".to_string(), "C++".to_string()],
+            rules: Vec::new(),
+        };
+        assert_eq!(vocabulary.prompt(None).as_deref(), Some("Acme, C++."));
+        assert_eq!(vocabulary.prompt(Some("A talk on compilers.")).as_deref(), Some("A talk on compilers. Acme, C++."));
+        assert_eq!(Vocabulary::default().prompt(Some("  ")), None);
+    }
+}
@@ -1,95 +1,627 @@
+use crate::download::{copy_with_sha256, download_verified, normalize_sha256, sha256_of_file};
 use crate::error::AppError;
+use crate::jobs::CancelToken;
+use crate::model_cache::ModelCache;
+use crate::progress::{ProgressReporter, ProgressStep};
+use crate::timestamp::Timestamp;
+use crate::vocabulary::Vocabulary;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::Write;
-use std::path::Path;
-use std::process::Command;
-use symphonia::core::audio::SampleBuffer;
-use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperState};
 use std::process;
+use crate::audio::{load_audio_file, AudioReader, ChannelSelection, WHISPER_SAMPLE_RATE};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptWord {
+    pub text: String,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub probability: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptSegment {
-    pub start: f64,
-    pub end: f64,
+    pub start: Timestamp,
+    pub end: Timestamp,
     pub text: String,
+    /// Word timings, empty for transcripts made before word timestamps were recorded
+    #[serde(default)]
+    pub words: Vec<TranscriptWord>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl TranscriptSegment {
+    /// Build a segment from whisper.cpp's centisecond timings
+    fn from_whisper(t0: i64, t1: i64, text: String) -> Self {
+        TranscriptSegment {
+            start: Timestamp::from_centiseconds(t0),
+            end: Timestamp::from_centiseconds(t1),
+            text,
+            words: Vec::new(),
+        }
+    }
+
+    /// Move the segment and its words later by `offset`
+    fn shift(&mut self, offset: Timestamp) {
+        self.start = self.start + offset;
+        self.end = self.end + offset;
+        for word in &mut self.words {
+            word.start = word.start + offset;
+            word.end = word.end + offset;
+        }
+    }
+}
+
+/// A text token as reported by whisper.cpp, with centisecond timings
+///
+/// Tokens are raw bytes: a multibyte character can be split across two tokens.
+struct RawToken {
+    bytes: Vec<u8>,
+    t0: i64,
+    t1: i64,
+    probability: f32,
+}
+
+/// Join sub-word tokens into words; a token starting with a space begins a new word
+fn words_from_tokens(tokens: &[RawToken]) -> Vec<TranscriptWord> {
+    // Bytes of each word are collected first and only decoded once the word is complete
+    let mut words: Vec<(Vec<u8>, TranscriptWord)> = Vec::new();
+    let mut token_counts: Vec<usize> = Vec::new();
+
+    for token in tokens {
+        let starts_word = token.bytes.first() == Some(&b' ') || words.is_empty();
+        if token.bytes.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        match words.last_mut() {
+            Some((bytes, word)) if !starts_word => {
+                bytes.extend_from_slice(&token.bytes);
+                word.end = Timestamp::from_centiseconds(token.t1);
+                word.probability += token.probability;
+                *token_counts.last_mut().unwrap() += 1;
+            }
+            _ => {
+                let word = TranscriptWord {
+                    text: String::new(),
+                    start: Timestamp::from_centiseconds(token.t0),
+                    end: Timestamp::from_centiseconds(token.t1),
+                    probability: token.probability,
+                };
+                words.push((token.bytes.clone(), word));
+                token_counts.push(1);
+            }
+        }
+    }
+
+    // Decode each word and report its mean token probability
+    words
+        .into_iter()
+        .zip(token_counts)
+        .map(|((bytes, mut word), count)| {
+            word.text = String::from_utf8_lossy(&bytes).trim().to_string();
+            word.probability /= count as f32;
+            word
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transcript {
     pub segments: Vec<TranscriptSegment>,
     pub text: String,
+    /// Language the audio was transcribed as, either requested or detected
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Confidence of the language detection, `None` when the language was given explicitly
+    #[serde(default)]
+    pub language_probability: Option<f32>,
+    /// Whether the text was translated to English
+    #[serde(default)]
+    pub translated: bool,
+}
+
+impl Transcript {
+    /// Recompute the full text after segments changed
+    pub fn rebuild_text(&mut self) {
+        self.text = self.segments.iter().map(|s| format!("{} ", s.text)).collect();
+    }
+}
+
+/// Options controlling how audio is transcribed
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TranscriptionOptions {
+    /// Spoken language code such as `"de"`, or `None`/`"auto"` to detect it
+    pub language: Option<String>,
+    /// Translate the speech to English instead of transcribing it verbatim
+    pub translate: bool,
+    /// Transcribe in windows of this many seconds instead of loading the whole file
+    pub chunk_secs: Option<f64>,
+    /// Audio shared by consecutive windows so words at the seams aren't lost
+    pub chunk_overlap_secs: f64,
+    /// Mix all channels or transcribe just one, e.g. a lav mic recorded on the left
+    pub channel: ChannelSelection,
+    /// A preset name such as `"accurate"`, or individual decoding settings
+    pub decoding: Decoding,
+    /// CPU threads for inference, defaults to the available cores up to 4
+    pub threads: Option<usize>,
+    /// Split segments longer than this many characters at word boundaries
+    pub max_segment_chars: Option<usize>,
+    /// Text Whisper treats as preceding the audio, useful for names and spelling
+    pub initial_prompt: Option<String>,
+    /// Project terms added to the prompt, and fixes applied to the finished transcript
+    pub vocabulary: Vocabulary,
+}
+
+impl Default for TranscriptionOptions {
+    fn default() -> Self {
+        TranscriptionOptions {
+            language: None,
+            translate: false,
+            chunk_secs: None,
+            chunk_overlap_secs: 5.0,
+            channel: ChannelSelection::Mix,
+            decoding: Decoding::default(),
+            threads: None,
+            max_segment_chars: None,
+            initial_prompt: None,
+            vocabulary: Vocabulary::default(),
+        }
+    }
+}
+
+/// Named sets of decoding settings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodingPreset {
+    /// Greedy decoding, the quickest option
+    Fast,
+    /// Beam search with more candidates and non-speech tokens suppressed
+    Accurate,
+}
+
+/// How Whisper picks tokens, accepted as either a preset name or an object of settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Decoding {
+    Preset(DecodingPreset),
+    Custom(DecodingOptions),
+}
+
+impl Default for Decoding {
+    fn default() -> Self {
+        Decoding::Preset(DecodingPreset::Fast)
+    }
+}
+
+impl Decoding {
+    pub fn options(&self) -> DecodingOptions {
+        match self {
+            Decoding::Preset(DecodingPreset::Fast) => DecodingOptions::default(),
+            Decoding::Preset(DecodingPreset::Accurate) => DecodingOptions {
+                beam_size: Some(5),
+                best_of: 5,
+                suppress_non_speech: true,
+                ..DecodingOptions::default()
+            },
+            Decoding::Custom(options) => options.clone(),
+        }
+    }
+}
+
+/// Sampling settings passed through to whisper.cpp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DecodingOptions {
+    /// Beams for beam search, or `None` to decode greedily
+    pub beam_size: Option<usize>,
+    /// Candidates sampled at each fallback temperature
+    pub best_of: usize,
+    /// Starting sampling temperature, 0 is deterministic
+    pub temperature: f32,
+    /// Added to the temperature each time a window fails Whisper's quality checks, 0 disables the fallback
+    pub temperature_increment: f32,
+    /// Keep Whisper from starting a segment with a blank
+    pub suppress_blank: bool,
+    /// Suppress tokens like music notes and bracketed sound descriptions
+    pub suppress_non_speech: bool,
+}
+
+impl Default for DecodingOptions {
+    fn default() -> Self {
+        DecodingOptions {
+            beam_size: None,
+            best_of: 1,
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            suppress_blank: true,
+            suppress_non_speech: false,
+        }
+    }
+}
+
+impl DecodingOptions {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.beam_size == Some(0) || self.best_of == 0 {
+            return Err(AppError::WhisperError("Beam size and best-of must be at least 1".to_string()));
+        }
+        if !(self.temperature >= 0.0 && self.temperature_increment >= 0.0) {
+            return Err(AppError::WhisperError("Temperatures must not be negative".to_string()));
+        }
+        Ok(())
+    }
+
+    fn sampling_strategy(&self) -> SamplingStrategy {
+        match self.beam_size {
+            Some(beam_size) => SamplingStrategy::BeamSearch {
+                beam_size: beam_size as i32,
+                // whisper.cpp's default, patience isn't implemented there yet
+                patience: -1.0,
+            },
+            None => SamplingStrategy::Greedy { best_of: self.best_of as i32 },
+        }
+    }
+}
+
+/// Convert seconds of 16kHz audio to a sample count
+fn secs_to_samples(secs: f64) -> usize {
+    (secs.max(0.0) * WHISPER_SAMPLE_RATE as f64) as usize
+}
+
+fn samples_to_timestamp(samples: usize) -> Timestamp {
+    Timestamp::from_secs(samples as f64 / WHISPER_SAMPLE_RATE as f64)
+}
+
+fn default_thread_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get().min(4))
+        .unwrap_or(1)
+}
+
+/// Detect the spoken language from the first 30 seconds of audio
+fn detect_language(
+    state: &mut WhisperState,
+    audio_data: &[f32],
+    threads: usize,
+) -> Result<(String, Option<f32>), AppError> {
+    let window = &audio_data[..audio_data.len().min(secs_to_samples(30.0))];
+
+    state.pcm_to_mel(window, threads)
+        .map_err(|e| AppError::WhisperError(format!("Failed to compute mel spectrogram: {}", e)))?;
+    let (lang_id, probabilities) = state.lang_detect(0, threads)
+        .map_err(|e| AppError::WhisperError(format!("Failed to detect language: {}", e)))?;
+
+    let language = whisper_rs::get_lang_str(lang_id)
+        .ok_or_else(|| AppError::WhisperError(format!("Unknown language id {}", lang_id)))?;
+    Ok((language.to_string(), probabilities.get(lang_id as usize).copied()))
 }
 
 /// Transcribe audio file using Whisper
-pub fn transcribe_audio(audio_path: &str) -> Result<String, AppError> {
-    // Add memory logging helper
-    let log_memory = |stage: &str| {
-        if let Ok(memory) = process::Command::new("ps")
-            .args(&["-o", "rss=", "-p", &process::id().to_string()])
-            .output() 
-        {
-            let memory_kb = String::from_utf8_lossy(&memory.stdout)
-                .trim()
-                .parse::<u64>()
-                .unwrap_or(0);
-            eprintln!("Memory usage at {}: {} MB", stage, memory_kb / 1024);
+pub fn transcribe_audio(
+    audio_path: &str,
+    model_id: &str,
+    models: &ModelCache,
+    options: &TranscriptionOptions,
+    progress: &ProgressReporter,
+    cancel: &CancelToken,
+) -> Result<String, AppError> {
+    let decoding = options.decoding.options();
+    decoding.validate()?;
+    if options.threads == Some(0) {
+        return Err(AppError::WhisperError("Thread count must be at least 1".to_string()));
+    }
+    let rules = options.vocabulary.compile()?;
+
+    let requested_language = options.language.as_deref().filter(|lang| *lang != "auto");
+    if let Some(lang) = requested_language {
+        if whisper_rs::get_lang_id(lang).is_none() {
+            return Err(AppError::WhisperError(format!("Unsupported language: {}", lang)));
         }
-    };
+    }
 
     // Log initial memory usage
     log_memory("start");
+    progress.report(0.0);
     
-    // Load Whisper model
-    let model_path = download_whisper_model()?;
-    log_memory("after model download");
-    
-    let ctx = WhisperContext::new_with_params(&model_path, WhisperContextParameters::default())
-        .map_err(|e| AppError::WhisperError(format!("Failed to load Whisper model: {}", e)))?;
+    // Load Whisper model, or reuse it if an earlier transcription already did
+    let model = models.load(model_id, progress, cancel)?;
+    let ctx = model.context();
     log_memory("after context creation");
+
+    let multilingual = ctx.is_multilingual();
+    if !multilingual && (options.translate || requested_language.is_some_and(|lang| lang != "en")) {
+        return Err(AppError::WhisperError(format!(
+            "Model {} is English-only, pick a multilingual model for other languages or translation",
+            model_id
+        )));
+    }
     
-    // Set up parameters
-    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-    params.set_print_special(false);
-    params.set_print_progress(false);
-    params.set_print_realtime(false);
-    params.set_print_timestamps(true);
-    params.set_language(Some("auto"));
-    params.set_translate(false);
-    
-    // Run inference
+    let mut state = model.checkout_state()?;
+    log_memory("after state creation");
+
+    let mut run = ChunkRun {
+        state: &mut state,
+        ctx,
+        options,
+        decoding,
+        threads: options.threads.unwrap_or_else(default_thread_count),
+        prompt: options.vocabulary.prompt(options.initial_prompt.as_deref()),
+        requested_language,
+        multilingual,
+        language: None,
+        progress,
+        cancel,
+    };
+
     let audio_path = Path::new(audio_path);
+    let segments = match options.chunk_secs {
+        Some(chunk_secs) => transcribe_chunked(&mut run, audio_path, chunk_secs, options.chunk_overlap_secs)?,
+        None => {
+            // Load audio data from file
+            let audio_data = load_audio_file(audio_path, options.channel)?;
+            log_memory("after audio load");
+            
+            // Log audio data size
+            eprintln!("Audio data size: {} MB", (audio_data.len() * std::mem::size_of::<f32>()) / (1024 * 1024));
+
+            let duration = samples_to_timestamp(audio_data.len());
+            run.transcribe(&audio_data, Timestamp::ZERO, duration)?
+        }
+    };
+    log_memory("after inference");
+    progress.report(1.0);
+
+    let (language, language_probability) = run.language.unwrap_or_else(|| ("en".to_string(), None));
+    let mut transcript = Transcript {
+        segments: Vec::new(),
+        text: String::new(),
+        language: Some(language),
+        language_probability,
+        translated: options.translate,
+    };
     
-    // Load audio data from file
-    let audio_data = load_audio_file(audio_path)?;
-    log_memory("after audio load");
+    for segment in segments {
+        transcript.text.push_str(&segment.text);
+        transcript.text.push(' ');
+        transcript.segments.push(segment);
+    }
+    rules.apply(&mut transcript);
     
-    // Log audio data size
-    eprintln!("Audio data size: {} MB", (audio_data.len() * std::mem::size_of::<f32>()) / (1024 * 1024));
+    // Save transcript to file
+    let output_path = audio_path.with_extension("json").to_string_lossy().to_string();
+    save_transcript(&output_path, &transcript)?;
     
-    let mut state = ctx.create_state()
-        .map_err(|e| AppError::WhisperError(format!("Failed to create Whisper state: {}", e)))?;
-    log_memory("after state creation");
+    Ok(output_path)
+}
+
+/// Read a transcript JSON file
+pub fn load_transcript(transcript_path: &str) -> Result<Transcript, AppError> {
+    let transcript_content = std::fs::read_to_string(transcript_path)?;
+    serde_json::from_str(&transcript_content)
+        .map_err(|e| AppError::WhisperError(format!("Failed to parse transcript: {}", e)))
+}
+
+/// Write a transcript as pretty-printed JSON
+pub fn save_transcript(transcript_path: &str, transcript: &Transcript) -> Result<(), AppError> {
+    let transcript_json = serde_json::to_string_pretty(transcript)
+        .map_err(|e| AppError::WhisperError(format!("Failed to serialize transcript: {}", e)))?;
     
-    eprintln!("Starting inference...");
-    state.full(params, &audio_data)
-        .map_err(|e| AppError::WhisperError(format!("Failed to run Whisper inference: {}", e)))?;
-    log_memory("after inference");
+    let mut file = File::create(transcript_path)?;
     
-    // Extract segments
+    file.write_all(transcript_json.as_bytes())?;
+    Ok(())
+}
+
+/// Log the resident memory of this process, to keep an eye on long transcriptions
+fn log_memory(stage: &str) {
+    if let Ok(memory) = process::Command::new("ps")
+        .args(["-o", "rss=", "-p", &process::id().to_string()])
+        .output() 
+    {
+        let memory_kb = String::from_utf8_lossy(&memory.stdout)
+            .trim()
+            .parse::<u64>()
+            .unwrap_or(0);
+        eprintln!("Memory usage at {}: {} MB", stage, memory_kb / 1024);
+    }
+}
+
+/// Stream the file through Whisper in overlapping windows so memory stays flat
+fn transcribe_chunked(
+    run: &mut ChunkRun,
+    audio_path: &Path,
+    chunk_secs: f64,
+    overlap_secs: f64,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    let chunk_len = secs_to_samples(chunk_secs).max(secs_to_samples(1.0));
+    // Keep the overlap well inside the window so every chunk makes progress
+    let overlap_len = secs_to_samples(overlap_secs).min(chunk_len / 2);
+
+    let mut reader = AudioReader::open(audio_path, run.options.channel)?;
+    let total_duration = reader.duration();
+
+    let mut segments = Vec::new();
+    let mut buffer: Vec<f32> = Vec::with_capacity(chunk_len);
+    let mut buffer_start = 0usize;
+
+    loop {
+        run.cancel.check()?;
+
+        let wanted = chunk_len - buffer.len();
+        let read = reader.read(&mut buffer, wanted)?;
+        // Only the carried-over overlap is left, which has already been transcribed
+        if read == 0 && (buffer_start > 0 || buffer.is_empty()) {
+            break;
+        }
+
+        let offset = samples_to_timestamp(buffer_start);
+        let duration = total_duration.unwrap_or_else(|| samples_to_timestamp(buffer_start + buffer.len()));
+        eprintln!(
+            "Transcribing chunk at {:.1}s ({:.1}s of audio)",
+            offset.as_secs(),
+            samples_to_timestamp(buffer.len()).as_secs()
+        );
+        let chunk_segments = run.transcribe(&buffer, offset, duration)?;
+        log_memory("after chunk");
+
+        // Segments before the middle of the overlap belong to the previous chunk
+        let boundary = offset + samples_to_timestamp(overlap_len / 2);
+        stitch_segments(&mut segments, chunk_segments, if buffer_start == 0 { Timestamp::ZERO } else { boundary });
+
+        if reader.is_finished() {
+            break;
+        }
+
+        // Carry the tail over as the start of the next window
+        let keep_from = buffer.len() - overlap_len;
+        buffer.drain(..keep_from);
+        buffer_start += keep_from;
+    }
+
+    Ok(segments)
+}
+
+/// Append a chunk's segments, resolving the overlap with what is already there
+///
+/// Existing segments starting at or after `boundary` are replaced by the new chunk
+/// where it heard something at the same time; otherwise they are kept. New segments
+/// that start inside an existing segment are duplicates: their words past the existing
+/// segment are kept when word timings are available, otherwise their start is moved
+/// to where the existing segment ends.
+fn stitch_segments(segments: &mut Vec<TranscriptSegment>, chunk_segments: Vec<TranscriptSegment>, boundary: Timestamp) {
+    segments.retain(|segment| {
+        segment.start < boundary
+            || !chunk_segments.iter().any(|new| new.start < segment.end && new.end > segment.start)
+    });
+    let mut covered_until = segments
+        .iter()
+        .filter(|segment| segment.start < boundary)
+        .map(|segment| segment.end)
+        .fold(boundary, |covered, end| if end > covered { end } else { covered });
+
+    for mut segment in chunk_segments {
+        if segment.start < covered_until {
+            if segment.words.is_empty() {
+                segment.start = covered_until;
+                if segment.end <= segment.start {
+                    continue;
+                }
+            } else {
+                segment.words.retain(|word| word.start >= covered_until);
+                let Some(first) = segment.words.first() else {
+                    continue;
+                };
+                segment.start = first.start;
+                segment.text = format!(
+                    " {}",
+                    segment.words.iter().map(|word| word.text.as_str()).collect::<Vec<_>>().join(" ")
+                );
+            }
+        }
+        if segment.end > covered_until {
+            covered_until = segment.end;
+        }
+        segments.push(segment);
+    }
+
+    // Kept segments from after the seam may now sit behind the new ones
+    segments.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+/// State shared by every window of one transcription
+struct ChunkRun<'a> {
+    state: &'a mut WhisperState,
+    ctx: &'a WhisperContext,
+    options: &'a TranscriptionOptions,
+    decoding: DecodingOptions,
+    threads: usize,
+    prompt: Option<String>,
+    requested_language: Option<&'a str>,
+    multilingual: bool,
+    /// Language and detection probability, settled by the first window
+    language: Option<(String, Option<f32>)>,
+    progress: &'a ProgressReporter,
+    cancel: &'a CancelToken,
+}
+
+impl ChunkRun<'_> {
+    /// Run Whisper over one window of audio starting at `offset` into a file of `total` length
+    fn transcribe(
+        &mut self,
+        audio_data: &[f32],
+        offset: Timestamp,
+        total: Timestamp,
+    ) -> Result<Vec<TranscriptSegment>, AppError> {
+        self.cancel.check()?;
+
+        if self.language.is_none() {
+            let detected = match self.requested_language {
+                Some(lang) => (lang.to_string(), None),
+                None if self.multilingual => detect_language(self.state, audio_data, self.threads)?,
+                None => ("en".to_string(), None),
+            };
+            eprintln!("Transcribing as language: {}", detected.0);
+            self.language = Some(detected);
+        }
+        let language = self.language.as_ref().map(|(lang, _)| lang.as_str());
+
+        // Set up parameters
+        let mut params = FullParams::new(self.decoding.sampling_strategy());
+        params.set_n_threads(self.threads as i32);
+        params.set_temperature(self.decoding.temperature);
+        params.set_temperature_inc(self.decoding.temperature_increment);
+        params.set_suppress_blank(self.decoding.suppress_blank);
+        params.set_suppress_non_speech_tokens(self.decoding.suppress_non_speech);
+        if let Some(max_chars) = self.options.max_segment_chars {
+            params.set_max_len(max_chars as i32);
+            params.set_split_on_word(true);
+        }
+        if let Some(prompt) = self.prompt.as_deref() {
+            // A NUL byte can't cross into C, and nothing useful follows one anyway
+            params.set_initial_prompt(prompt.split('\0').next().unwrap_or_default());
+        }
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(true);
+        params.set_language(language);
+        params.set_translate(self.options.translate);
+        params.set_token_timestamps(true);
+
+        // Forward Whisper's percentage callback as progress through the whole file
+        let reporter = self.progress.clone();
+        let chunk_secs = samples_to_timestamp(audio_data.len()).as_secs();
+        let (offset_secs, total_secs) = (offset.as_secs(), total.as_secs().max(chunk_secs));
+        params.set_progress_callback_safe(move |percent: i32| {
+            reporter.report((offset_secs + chunk_secs * percent as f64 / 100.0) / total_secs)
+        });
+
+        // Let whisper.cpp stop between decoding steps once the job is cancelled
+        let token = self.cancel.clone();
+        params.set_abort_callback_safe(move || token.is_cancelled());
+
+        eprintln!("Starting inference...");
+        let result = self.state.full(params, audio_data);
+        self.cancel.check()?;
+        result.map_err(|e| AppError::WhisperError(format!("Failed to run Whisper inference: {}", e)))?;
+
+        let mut segments = collect_segments(self.state, self.ctx)?;
+        for segment in &mut segments {
+            segment.shift(offset);
+        }
+        Ok(segments)
+    }
+}
+
+/// Read the segments and word timings of the last inference out of the Whisper state
+fn collect_segments(state: &WhisperState, ctx: &WhisperContext) -> Result<Vec<TranscriptSegment>, AppError> {
+    let eot_token = ctx.token_eot();
     let num_segments = state.full_n_segments()
         .map_err(|e| AppError::WhisperError(format!("Failed to get number of segments: {}", e)))?;
-    
-    let mut transcript = Transcript {
-        segments: Vec::new(),
-        text: String::new(),
-    };
-    
+
+    let mut segments = Vec::with_capacity(num_segments as usize);
     for i in 0..num_segments {
         let segment_text = state.full_get_segment_text(i)
             .map_err(|e| AppError::WhisperError(format!("Failed to get segment text: {}", e)))?;
@@ -100,248 +632,638 @@ pub fn transcribe_audio(audio_path: &str) -> Result<String, AppError> {
         let end_timestamp = state.full_get_segment_t1(i)
             .map_err(|e| AppError::WhisperError(format!("Failed to get segment end time: {}", e)))?;
         
-        let segment = TranscriptSegment {
-            start: start_timestamp as f64,
-            end: end_timestamp as f64,
-            text: segment_text.clone(),
-        };
-        
-        transcript.segments.push(segment);
-        transcript.text.push_str(&segment_text);
-        transcript.text.push(' ');
+        let mut segment = TranscriptSegment::from_whisper(start_timestamp, end_timestamp, segment_text);
+
+        let num_tokens = state.full_n_tokens(i)
+            .map_err(|e| AppError::WhisperError(format!("Failed to get number of tokens: {}", e)))?;
+        let mut tokens = Vec::with_capacity(num_tokens as usize);
+        for j in 0..num_tokens {
+            let data = state.full_get_token_data(i, j)
+                .map_err(|e| AppError::WhisperError(format!("Failed to get token data: {}", e)))?;
+            // Timestamp and control tokens sit at or above end-of-text in the vocabulary
+            if data.id >= eot_token {
+                continue;
+            }
+            let bytes = ctx.token_to_cstr(data.id)
+                .map_err(|e| AppError::WhisperError(format!("Failed to get token text: {}", e)))?
+                .to_bytes()
+                .to_vec();
+            tokens.push(RawToken {
+                bytes,
+                t0: data.t0,
+                t1: data.t1,
+                probability: data.p,
+            });
+        }
+        segment.words = words_from_tokens(&tokens);
+        segments.push(segment);
     }
-    
-    // Save transcript to file
-    let output_path = audio_path.with_extension("json");
-    let transcript_json = serde_json::to_string_pretty(&transcript)
-        .map_err(|e| AppError::WhisperError(format!("Failed to serialize transcript: {}", e)))?;
-    
-    let mut file = File::create(&output_path)
-        .map_err(|e| AppError::IoError(e))?;
-    
-    file.write_all(transcript_json.as_bytes())
-        .map_err(|e| AppError::IoError(e))?;
-    
-    Ok(output_path.to_string_lossy().to_string())
+
+    Ok(segments)
+}
+
+/// Model used when a transcription doesn't ask for a specific one
+pub const DEFAULT_MODEL: &str = "tiny.en";
+
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// A ggml model published in the whisper.cpp model repository
+struct KnownModel {
+    id: &'static str,
+    size_mb: u32,
+    english_only: bool,
+    quantization: Option<&'static str>,
+}
+
+const fn known(id: &'static str, size_mb: u32, english_only: bool, quantization: Option<&'static str>) -> KnownModel {
+    KnownModel { id, size_mb, english_only, quantization }
 }
 
-/// Download Whisper model if not already present
-fn download_whisper_model() -> Result<String, AppError> {
+const KNOWN_MODELS: &[KnownModel] = &[
+    known("tiny", 75, false, None),
+    known("tiny.en", 75, true, None),
+    known("tiny-q5_1", 31, false, Some("q5_1")),
+    known("tiny.en-q5_1", 31, true, Some("q5_1")),
+    known("tiny-q8_0", 42, false, Some("q8_0")),
+    known("base", 142, false, None),
+    known("base.en", 142, true, None),
+    known("base-q5_1", 57, false, Some("q5_1")),
+    known("base.en-q5_1", 57, true, Some("q5_1")),
+    known("base-q8_0", 78, false, Some("q8_0")),
+    known("small", 466, false, None),
+    known("small.en", 466, true, None),
+    known("small-q5_1", 181, false, Some("q5_1")),
+    known("small.en-q5_1", 181, true, Some("q5_1")),
+    known("small-q8_0", 252, false, Some("q8_0")),
+    known("medium", 1500, false, None),
+    known("medium.en", 1500, true, None),
+    known("medium-q5_0", 514, false, Some("q5_0")),
+    known("medium.en-q5_0", 514, true, Some("q5_0")),
+    known("medium-q8_0", 785, false, Some("q8_0")),
+    known("large-v1", 2900, false, None),
+    known("large-v2", 2900, false, None),
+    known("large-v2-q5_0", 1080, false, Some("q5_0")),
+    known("large-v3", 2900, false, None),
+    known("large-v3-q5_0", 1080, false, Some("q5_0")),
+    known("large-v3-turbo", 1500, false, None),
+    known("large-v3-turbo-q5_0", 547, false, Some("q5_0")),
+];
+
+/// SHA-256 of known model files, checked before a download or a cached file is used
+///
+/// Models missing here are checked against the checksum the server publishes
+/// when fetched on demand; the download command needs a checksum or explicit consent.
+const PINNED_SHA256: &[(&str, &str)] = &[];
+
+fn pinned_sha256(model_id: &str) -> Option<&'static str> {
+    PINNED_SHA256.iter().find(|(id, _)| *id == model_id).map(|(_, sha)| *sha)
+}
+
+/// A Whisper model as reported to the frontend
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhisperModelInfo {
+    pub id: String,
+    pub file_name: String,
+    pub size_mb: Option<u32>,
+    pub english_only: bool,
+    pub quantization: Option<String>,
+    pub installed: bool,
+    pub known: bool,
+}
+
+/// Directory where Whisper models are cached
+fn model_dir() -> Result<PathBuf, AppError> {
     let model_dir = dirs::cache_dir()
         .ok_or_else(|| AppError::WhisperError("Failed to get cache directory".to_string()))?
         .join("whisper-models");
-    
-    std::fs::create_dir_all(&model_dir)
-        .map_err(|e| AppError::IoError(e))?;
-    
-    let model_path = model_dir.join("ggml-tiny.en.bin");
-    
-    if !model_path.exists() {
-        // Download the model
-        let url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.en.bin";
-        
-        let status = Command::new("curl")
-            .arg("-L")
-            .arg(url)
-            .arg("-o")
-            .arg(&model_path)
-            .status()
-            .map_err(|e| AppError::WhisperError(format!("Failed to download Whisper model: {}", e)))?;
-        
-        if !status.success() {
-            return Err(AppError::WhisperError("Failed to download Whisper model".to_string()));
+
+    std::fs::create_dir_all(&model_dir)?;
+
+    Ok(model_dir)
+}
+
+fn model_file_name(model_id: &str) -> String {
+    format!("ggml-{}.bin", model_id)
+}
+
+/// Reject ids that could escape the model directory
+fn validate_model_id(model_id: &str) -> Result<(), AppError> {
+    let valid = !model_id.is_empty()
+        && !model_id.starts_with('.')
+        && model_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::WhisperError(format!("Invalid model id: {}", model_id)))
+    }
+}
+
+/// Path a model is (or would be) stored at in the cache
+fn model_path(model_id: &str) -> Result<PathBuf, AppError> {
+    validate_model_id(model_id)?;
+    Ok(model_dir()?.join(model_file_name(model_id)))
+}
+
+/// List known models and any other models installed in the cache
+pub fn list_models() -> Result<Vec<WhisperModelInfo>, AppError> {
+    let model_dir = model_dir()?;
+
+    let mut models: Vec<WhisperModelInfo> = KNOWN_MODELS
+        .iter()
+        .map(|model| WhisperModelInfo {
+            id: model.id.to_string(),
+            file_name: model_file_name(model.id),
+            size_mb: Some(model.size_mb),
+            english_only: model.english_only,
+            quantization: model.quantization.map(str::to_string),
+            installed: model_dir.join(model_file_name(model.id)).is_file(),
+            known: true,
+        })
+        .collect();
+
+    // Models that were added by hand still show up so they can be used or deleted
+    for entry in std::fs::read_dir(&model_dir)? {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        let Some(id) = file_name.strip_prefix("ggml-").and_then(|name| name.strip_suffix(".bin")) else {
+            continue;
+        };
+        if validate_model_id(id).is_err() || KNOWN_MODELS.iter().any(|model| model.id == id) {
+            continue;
         }
+        models.push(WhisperModelInfo {
+            id: id.to_string(),
+            file_name: file_name.clone(),
+            size_mb: None,
+            english_only: id.contains(".en"),
+            quantization: None,
+            installed: true,
+            known: false,
+        });
     }
-    
+
+    Ok(models)
+}
+
+/// Where to fetch a model from and the checksum it must match
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelSource {
+    /// Base URL serving `ggml-<id>.bin` files, defaults to `WHISPER_MODEL_MIRROR` or Hugging Face
+    pub mirror_url: Option<String>,
+    /// Expected SHA-256, defaults to the pinned checksum of known models
+    pub sha256: Option<String>,
+    /// Accept the checksum the server publishes for models without a pinned one
+    #[serde(default)]
+    pub trust_server_checksum: bool,
+}
+
+impl ModelSource {
+    fn base_url(&self) -> String {
+        self.mirror_url
+            .clone()
+            .or_else(|| std::env::var("WHISPER_MODEL_MIRROR").ok())
+            .unwrap_or_else(|| MODEL_BASE_URL.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+}
+
+/// Resolve a model id to an installed model file, downloading known models on demand
+pub fn resolve_model(model_id: &str, progress: &ProgressReporter, cancel: &CancelToken) -> Result<String, AppError> {
+    resolve_model_file(model_id, &model_path(model_id)?, &on_demand_source(), progress, cancel)
+}
+
+/// Where models needed for a transcription come from, without asking the user for a checksum
+fn on_demand_source() -> ModelSource {
+    ModelSource {
+        trust_server_checksum: true,
+        ..ModelSource::default()
+    }
+}
+
+fn resolve_model_file(
+    model_id: &str,
+    path: &Path,
+    source: &ModelSource,
+    progress: &ProgressReporter,
+    cancel: &CancelToken,
+) -> Result<String, AppError> {
+    if verify_installed_model(model_id, path)? {
+        return Ok(path.to_string_lossy().to_string());
+    }
+    if path.is_file() && !KNOWN_MODELS.iter().any(|model| model.id == model_id) {
+        return Err(AppError::WhisperError(format!(
+            "Whisper model {} is damaged, please import it again",
+            model_id
+        )));
+    }
+    let download_progress = progress.for_step(ProgressStep::DownloadModel);
+    download_model_file(model_id, path, source, &download_progress, cancel)
+}
+
+/// Download a Whisper model if not already present
+pub fn download_whisper_model(
+    model_id: &str,
+    source: &ModelSource,
+    progress: &ProgressReporter,
+    cancel: &CancelToken,
+) -> Result<String, AppError> {
+    download_model_file(model_id, &model_path(model_id)?, source, progress, cancel)
+}
+
+fn download_model_file(
+    model_id: &str,
+    model_path: &Path,
+    source: &ModelSource,
+    progress: &ProgressReporter,
+    cancel: &CancelToken,
+) -> Result<String, AppError> {
+    if !KNOWN_MODELS.iter().any(|model| model.id == model_id) {
+        return Err(AppError::WhisperError(format!("Unknown Whisper model: {}", model_id)));
+    }
+
+    if verify_installed_model(model_id, model_path)? {
+        return Ok(model_path.to_string_lossy().to_string());
+    }
+    if model_path.exists() {
+        eprintln!("Model file {} is incomplete or damaged, downloading it again", model_path.display());
+        remove_model_files(model_path)?;
+    }
+
+    let expected = source.sha256.as_deref().or_else(|| pinned_sha256(model_id));
+    if expected.is_none() && !source.trust_server_checksum {
+        return Err(AppError::DownloadError(format!(
+            "No pinned checksum for Whisper model {}, provide a SHA-256 or accept the server's checksum",
+            model_id
+        )));
+    }
+
+    let url = format!("{}/{}", source.base_url(), model_file_name(model_id));
+    let sha256 = download_verified(&url, model_path, expected, progress, cancel)?;
+    record_checksum(model_path, &sha256)?;
+
     Ok(model_path.to_string_lossy().to_string())
 }
 
-/// Analyze transcript to determine strategic cut points
-pub fn analyze_transcript_for_cuts(transcript_path: &str) -> Result<Vec<crate::video_processor::CutPoint>, AppError> {
-    let transcript_content = std::fs::read_to_string(transcript_path)
-        .map_err(|e| AppError::IoError(e))?;
-    
-    let transcript: Transcript = serde_json::from_str(&transcript_content)
-        .map_err(|e| AppError::WhisperError(format!("Failed to parse transcript: {}", e)))?;
-    
-    // Simple algorithm to find natural breaks (pauses)
-    let mut cut_points = Vec::new();
-    let mut current_start = 0.0;
-    
-    for i in 1..transcript.segments.len() {
-        let prev_segment = &transcript.segments[i - 1];
-        let curr_segment = &transcript.segments[i];
-        
-        // If there's a pause of more than 1 second, consider it a cut point
-        let pause_duration = curr_segment.start - prev_segment.end;
-        if pause_duration > 1.0 {
-            cut_points.push(crate::video_processor::CutPoint {
-                start_time: current_start,
-                end_time: prev_segment.end,
-                description: format!("Segment {}", cut_points.len() + 1),
-            });
-            
-            current_start = curr_segment.start;
+/// Sidecar file holding the checksum and size a model file had when it was verified
+fn checksum_path(model_path: &Path) -> PathBuf {
+    model_path.with_extension("bin.sha256")
+}
+
+fn record_checksum(model_path: &Path, sha256: &str) -> Result<(), AppError> {
+    let size = std::fs::metadata(model_path)?.len();
+    std::fs::write(checksum_path(model_path), format!("{} {}\n", sha256, size))?;
+    Ok(())
+}
+
+/// Whether an installed model file is complete
+///
+/// A file with a recorded checksum only needs its size checked. Older files are
+/// hashed once against the pinned checksum; without one, only the header can be checked.
+fn verify_installed_model(model_id: &str, model_path: &Path) -> Result<bool, AppError> {
+    if !model_path.is_file() {
+        return Ok(false);
+    }
+
+    let size = std::fs::metadata(model_path)?.len();
+    let recorded = std::fs::read_to_string(checksum_path(model_path)).ok();
+    if let Some(recorded_size) = recorded.as_deref().and_then(|r| r.split_whitespace().nth(1)) {
+        return Ok(recorded_size.parse::<u64>().ok() == Some(size));
+    }
+
+    match pinned_sha256(model_id) {
+        Some(expected) => {
+            eprintln!("Verifying checksum of {}", model_path.display());
+            let actual = sha256_of_file(model_path)?;
+            if actual != expected {
+                return Ok(false);
+            }
+            record_checksum(model_path, &actual)?;
+            Ok(true)
         }
+        None => Ok(validate_ggml_header(model_path).is_ok()),
     }
-    
-    // Add the final segment
-    if let Some(last_segment) = transcript.segments.last() {
-        cut_points.push(crate::video_processor::CutPoint {
-            start_time: current_start,
-            end_time: last_segment.end,
-            description: format!("Segment {}", cut_points.len() + 1),
-        });
+}
+
+/// Remove a model file along with its recorded checksum
+fn remove_model_files(model_path: &Path) -> Result<(), AppError> {
+    std::fs::remove_file(model_path)?;
+    let _ = std::fs::remove_file(checksum_path(model_path));
+    Ok(())
+}
+
+/// Magic number at the start of every ggml Whisper model, as a little-endian u32
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+
+/// Check that a file looks like a ggml Whisper model before it is loaded
+fn validate_ggml_header(path: &Path) -> Result<(), AppError> {
+    let mut header = [0u8; 44];
+    File::open(path)?
+        .read_exact(&mut header)
+        .map_err(|_| AppError::WhisperError("File is too small to be a Whisper model".to_string()))?;
+
+    let field = |index: usize| {
+        let offset = index * 4;
+        u32::from_le_bytes([header[offset], header[offset + 1], header[offset + 2], header[offset + 3]])
+    };
+
+    if field(0) != GGML_MAGIC {
+        return Err(AppError::WhisperError("File is not a ggml Whisper model (bad magic number)".to_string()));
     }
-    
-    Ok(cut_points)
+
+    // Header layout: magic, n_vocab, n_audio_ctx, n_audio_state, n_audio_head, n_audio_layer,
+    // n_text_ctx, n_text_state, n_text_head, n_text_layer, n_mels
+    let n_vocab = field(1);
+    let n_mels = field(10);
+    if n_vocab == 0 || !(n_mels == 80 || n_mels == 128) {
+        return Err(AppError::WhisperError(format!(
+            "Unexpected Whisper model header (n_vocab {}, n_mels {})",
+            n_vocab, n_mels
+        )));
+    }
+
+    Ok(())
 }
 
-fn load_audio_file(path: &Path) -> Result<Vec<f32>, AppError> {
-    eprintln!("Starting audio file load from: {}", path.display());
-    // Open the media source
-    let file = File::open(path)
-        .map_err(|e| AppError::WhisperError(format!("Failed to open audio file: {}", e)))?;
-    
-    // Create a media source stream
-    let mss = MediaSourceStream::new(
-        Box::new(file),
-        Default::default(),
-    );
-    
-    // Create a hint to help the format registry guess what format the media is
-    let mut hint = Hint::new();
-    if let Some(extension) = path.extension() {
-        if let Some(ext_str) = extension.to_str() {
-            hint.with_extension(ext_str);
-        }
+/// Copy a model file from disk into the cache so it can be used without network access
+///
+/// The id defaults to the `<id>` part of a `ggml-<id>.bin` file name.
+pub fn import_whisper_model(
+    source_path: &str,
+    model_id: Option<&str>,
+    sha256: Option<&str>,
+) -> Result<String, AppError> {
+    let source_path = Path::new(source_path);
+    let model_id = match model_id {
+        Some(id) => id.to_string(),
+        None => source_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("ggml-"))
+            .and_then(|name| name.strip_suffix(".bin"))
+            .map(str::to_string)
+            .ok_or_else(|| AppError::WhisperError(
+                "Cannot derive a model id from the file name, please provide one".to_string(),
+            ))?,
+    };
+
+    let expected = sha256
+        .map(|sha| normalize_sha256(sha)
+            .ok_or_else(|| AppError::WhisperError(format!("Invalid SHA-256 checksum: {}", sha))))
+        .transpose()?;
+
+    import_model_file(source_path, &model_id, &model_path(&model_id)?, expected.as_deref())?;
+    Ok(model_id)
+}
+
+/// Copy a validated model file to `model_path`, leaving nothing behind if it fails
+///
+/// A damaged model already at `model_path` is replaced; a working one is kept.
+fn import_model_file(source_path: &Path, model_id: &str, model_path: &Path, expected: Option<&str>) -> Result<(), AppError> {
+    if verify_installed_model(model_id, model_path)? {
+        return Err(AppError::WhisperError(format!("Whisper model {} is already installed", model_id)));
     }
-    
-    // Use the default options for format reader
-    let format_opts = FormatOptions::default();
-    
-    // Use the default options for metadata reader
-    let metadata_opts = MetadataOptions::default();
-    
-    // Use the default options for the decoder
-    let decoder_opts = DecoderOptions::default();
-    
-    // Probe the media source
-    let probed = symphonia::default::get_probe()
-        .format(&hint, mss, &format_opts, &metadata_opts)
-        .map_err(|e| AppError::WhisperError(format!("Failed to probe media format: {}", e)))?;
-    
-    // Get the format reader
-    let mut format = probed.format;
-    
-    // Find the first audio track
-    let track = format
-        .tracks()
-        .iter()
-        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-        .ok_or_else(|| AppError::WhisperError("No audio track found".to_string()))?;
-    
-    // Create a decoder for the track
-    let mut decoder = symphonia::default::get_codecs()
-        .make(&track.codec_params, &decoder_opts)
-        .map_err(|e| AppError::WhisperError(format!("Failed to create decoder: {}", e)))?;
-    
-    // Store the track identifier
-    let track_id = track.id;
-    
-    // Create a sample buffer
-    let mut sample_buf = None;
-    
-    // Create a buffer for the decoded audio
-    let mut audio_data = Vec::new();
-    
-    // Decode the audio
-    loop {
-        // Get the next packet from the format reader
-        let packet = match format.next_packet() {
-            Ok(packet) => packet,
-            Err(symphonia::core::errors::Error::IoError(ref e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-            Err(e) => return Err(AppError::WhisperError(format!("Error reading packet: {}", e))),
-        };
-        
-        // Skip packets from other tracks
-        if packet.track_id() != track_id {
-            continue;
-        }
-        
-        // Decode the packet
-        let decoded = match decoder.decode(&packet) {
-            Ok(decoded) => decoded,
-            Err(e) => {
-                eprintln!("Error decoding packet: {}", e);
-                continue;
+    validate_ggml_header(source_path)?;
+
+    // Copy next to the final location so the rename, which replaces a damaged file, is atomic
+    let import_path = model_path.with_extension("bin.import");
+    let imported = copy_with_sha256(source_path, &import_path).and_then(|actual| {
+        if let Some(expected) = expected {
+            if actual != expected {
+                return Err(AppError::WhisperError(format!(
+                    "Checksum mismatch: expected {}, got {}",
+                    expected, actual
+                )));
             }
-        };
-        
-        // Get the audio buffer specification
-        let spec = *decoded.spec();
-        
-        // Create a sample buffer if needed
-        if sample_buf.is_none() {
-            sample_buf = Some(SampleBuffer::<f32>::new(decoded.capacity() as u64, spec));
         }
-        
-        // Copy the decoded audio to the sample buffer
-        if let Some(buf) = &mut sample_buf {
-            buf.copy_planar_ref(decoded);
-            
-            // Convert the audio to f32 samples and add to our buffer
-            audio_data.extend_from_slice(buf.samples());
+        std::fs::rename(&import_path, model_path)?;
+        Ok(actual)
+    });
+
+    match imported {
+        Ok(actual) => record_checksum(model_path, &actual),
+        Err(e) => {
+            let _ = std::fs::remove_file(&import_path);
+            Err(e)
         }
     }
-    
-    // If we have a stereo file, convert to mono by averaging channels
-    let spec = decoder.codec_params();
-    if spec.channels.unwrap().count() > 1 {
-        let channels = spec.channels.unwrap().count() as usize;
-        let samples_per_channel = audio_data.len() / channels;
-        let mut mono_data = Vec::with_capacity(samples_per_channel);
-        
-        for i in 0..samples_per_channel {
-            let mut sample_sum = 0.0;
-            for c in 0..channels {
-                sample_sum += audio_data[i + c * samples_per_channel];
-            }
-            mono_data.push(sample_sum / channels as f32);
-        }
-        
-        audio_data = mono_data;
+}
+
+/// Remove an installed model from the cache
+pub fn delete_whisper_model(model_id: &str) -> Result<(), AppError> {
+    let model_path = model_path(model_id)?;
+    if !model_path.is_file() {
+        return Err(AppError::WhisperError(format!("Whisper model {} is not installed", model_id)));
     }
-    
-    // Resample to 16kHz if needed
-    let sample_rate = spec.sample_rate.unwrap_or(16000);
-    if sample_rate != 16000 {
-        // Simple resampling - in a real app you'd want to use a proper resampler
-        let ratio = 16000.0 / sample_rate as f32;
-        let new_len = (audio_data.len() as f32 * ratio) as usize;
-        let mut resampled = Vec::with_capacity(new_len);
-        
-        for i in 0..new_len {
-            let src_idx = (i as f32 / ratio) as usize;
-            if src_idx < audio_data.len() {
-                resampled.push(audio_data[src_idx]);
-            } else {
-                break;
-            }
+
+    remove_model_files(&model_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoding_accepts_preset_names_and_settings() {
+        let options: TranscriptionOptions = serde_json::from_str(r#"{"decoding": "accurate"}"#).unwrap();
+        assert_eq!(options.decoding.options().beam_size, Some(5));
+
+        let options: TranscriptionOptions =
+            serde_json::from_str(r#"{"decoding": {"bestOf": 3, "temperatureIncrement": 0}}"#).unwrap();
+        let decoding = options.decoding.options();
+        assert_eq!((decoding.beam_size, decoding.best_of), (None, 3));
+        assert_eq!(decoding.temperature_increment, 0.0);
+        assert!(decoding.suppress_blank);
+
+        let defaults = TranscriptionOptions::default().decoding.options();
+        assert!(matches!(defaults.sampling_strategy(), SamplingStrategy::Greedy { best_of: 1 }));
+        assert!(serde_json::from_str::<TranscriptionOptions>(r#"{"decoding": "slow"}"#).is_err());
+    }
+
+    #[test]
+    fn segment_centiseconds_are_converted_to_seconds() {
+        // The synthetic clip's segments as whisper.cpp reports them
+        let segments = [
+            TranscriptSegment::from_whisper(50, 200, "First line.".to_string()),
+            TranscriptSegment::from_whisper(230, 410, "Second line.".to_string()),
+            TranscriptSegment::from_whisper(600, 825, "After a pause.".to_string()),
+        ];
+        let expected = crate::cuts::test_support::synthetic_clip_transcript();
+        for (segment, expected) in segments.iter().zip(&expected.segments) {
+            assert_eq!((segment.start, segment.end), (expected.start, expected.end));
         }
-        
-        audio_data = resampled;
+
+        let json = serde_json::to_value(&segments[2]).unwrap();
+        assert_eq!(json["start"], 6.0);
+        assert_eq!(json["end"], 8.25);
     }
-    
-    // Add size logging for audio data
-    eprintln!("Loaded audio data size: {} samples", audio_data.len());
-    if spec.channels.unwrap().count() > 1 {
-        eprintln!("Converting from {} channels to mono", spec.channels.unwrap().count());
+
+    #[test]
+    fn tokens_are_joined_into_words() {
+        let token = |text: &str, t0, t1, probability| RawToken {
+            bytes: text.as_bytes().to_vec(),
+            t0,
+            t1,
+            probability,
+        };
+        let words = words_from_tokens(&[
+            token(" Hel", 100, 120, 0.8),
+            token("lo", 120, 140, 0.6),
+            token(",", 140, 141, 1.0),
+            token(" world", 150, 190, 0.9),
+        ]);
+
+        let summary: Vec<(&str, f64, f64)> = words
+            .iter()
+            .map(|w| (w.text.as_str(), w.start.as_secs(), w.end.as_secs()))
+            .collect();
+        assert_eq!(summary, vec![("Hello,", 1.0, 1.41), ("world", 1.5, 1.9)]);
+        assert!((words[0].probability - 0.8).abs() < 1e-6);
     }
-    
-    if sample_rate != 16000 {
-        eprintln!("Resampling from {}Hz to 16000Hz", sample_rate);
+
+    #[test]
+    fn characters_split_across_tokens_are_decoded_whole() {
+        let token = |bytes: &[u8], t0, t1| RawToken {
+            bytes: bytes.to_vec(),
+            t0,
+            t1,
+            probability: 1.0,
+        };
+        // "é" is 0xC3 0xA9 and "日" is 0xE6 0x97 0xA5, each split over two tokens
+        let words = words_from_tokens(&[
+            token(b" caf\xC3", 0, 20),
+            token(b"\xA9", 20, 30),
+            token(b" \xE6\x97", 40, 50),
+            token(b"\xA5", 50, 60),
+        ]);
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, ["café", "日"]);
     }
-    
-    Ok(audio_data)
-} 
\ No newline at end of file
+
+    #[test]
+    fn overlapping_chunks_are_stitched_without_duplicates() {
+        let seg = |start: f64, end: f64, text: &str| {
+            let mut segment = TranscriptSegment::from_whisper(0, 0, text.to_string());
+            segment.start = Timestamp::from_secs(start);
+            segment.end = Timestamp::from_secs(end);
+            segment
+        };
+
+        let mut segments = Vec::new();
+        stitch_segments(&mut segments, vec![seg(0.0, 20.0, "a"), seg(21.0, 29.0, "b"), seg(29.0, 30.0, "c")], Timestamp::ZERO);
+        // Second window starts at 25s with 5s of overlap, so the seam is at 27.5s
+        stitch_segments(
+            &mut segments,
+            vec![seg(25.2, 28.5, "b again"), seg(28.0, 29.5, "c again"), seg(29.5, 40.0, "d")],
+            Timestamp::from_secs(27.5),
+        );
+
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        // "b" straddles the seam and is kept, "c" is replaced by the new window's take,
+        // which starts where "b" ends since it has no word timings
+        assert_eq!(texts, vec!["a", "b", "c again", "d"]);
+        assert_eq!(segments[2].start, Timestamp::from_secs(29.0));
+
+        // With word timings only the words already covered are trimmed
+        let mut segments = vec![seg(0.0, 10.0, "one two")];
+        let mut straddling = seg(9.0, 12.0, "two three");
+        straddling.words = vec![
+            TranscriptWord { text: "two".to_string(), start: Timestamp::from_secs(9.0), end: Timestamp::from_secs(9.8), probability: 1.0 },
+            TranscriptWord { text: "three".to_string(), start: Timestamp::from_secs(10.5), end: Timestamp::from_secs(12.0), probability: 1.0 },
+        ];
+        stitch_segments(&mut segments, vec![straddling], Timestamp::from_secs(8.0));
+        assert_eq!(segments[1].text, " three");
+        assert_eq!(segments[1].start, Timestamp::from_secs(10.5));
+
+        let mut segments = vec![seg(0.0, 10.0, "a")];
+        stitch_segments(&mut segments, vec![seg(12.0, 14.0, "e")], Timestamp::from_secs(11.0));
+        assert_eq!(segments.len(), 2);
+
+        // A segment past the seam that the new window didn't hear again is not lost
+        let mut segments = vec![seg(0.0, 10.0, "a"), seg(12.0, 13.0, "f")];
+        stitch_segments(&mut segments, vec![seg(14.0, 15.0, "g")], Timestamp::from_secs(11.0));
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "f", "g"]);
+    }
+
+    /// A ggml header with plausible dimensions followed by some weights
+    fn fake_model() -> Vec<u8> {
+        let header = [GGML_MAGIC, 51864, 1500, 384, 6, 4, 448, 384, 6, 4, 80];
+        let mut bytes: Vec<u8> = header.iter().flat_map(|field| field.to_le_bytes()).collect();
+        bytes.extend((0..4096u32).map(|i| (i % 251) as u8));
+        bytes
+    }
+
+    #[test]
+    fn recorded_checksums_catch_truncated_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ggml-custom.bin");
+        std::fs::write(&path, fake_model()).unwrap();
+
+        // Without a record only the header can be checked
+        assert!(verify_installed_model("custom", &path).unwrap());
+
+        record_checksum(&path, &sha256_of_file(&path).unwrap()).unwrap();
+        assert!(verify_installed_model("custom", &path).unwrap());
+
+        let truncated = &fake_model()[..2048];
+        std::fs::write(&path, truncated).unwrap();
+        assert!(!verify_installed_model("custom", &path).unwrap());
+
+        remove_model_files(&path).unwrap();
+        assert!(!checksum_path(&path).exists());
+        assert!(!verify_installed_model("custom", &path).unwrap());
+    }
+
+    #[test]
+    fn imports_are_validated_and_cleaned_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("download.bin");
+        let cache = tempfile::tempdir().unwrap();
+        let model = cache.path().join("ggml-custom.bin");
+        let leftovers = || std::fs::read_dir(cache.path()).unwrap().count();
+
+        std::fs::write(&source, b"not a model at all, just some text").unwrap();
+        assert!(import_model_file(&source, "custom", &model, None).is_err());
+        assert_eq!(leftovers(), 0);
+
+        std::fs::write(&source, fake_model()).unwrap();
+        let wrong = "0".repeat(64);
+        assert!(import_model_file(&source, "custom", &model, Some(&wrong)).is_err());
+        assert_eq!(leftovers(), 0);
+
+        let sha = sha256_of_file(&source).unwrap();
+        import_model_file(&source, "custom", &model, Some(&sha)).unwrap();
+        assert_eq!(std::fs::read(&model).unwrap(), fake_model());
+        assert!(!model.with_extension("bin.import").exists());
+        assert!(verify_installed_model("custom", &model).unwrap());
+
+        // A working model stays, a damaged one can be imported again
+        assert!(import_model_file(&source, "custom", &model, None).is_err());
+        std::fs::write(&model, &fake_model()[..2048]).unwrap();
+        import_model_file(&source, "custom", &model, None).unwrap();
+        assert_eq!(std::fs::read(&model).unwrap(), fake_model());
+        assert!(verify_installed_model("custom", &model).unwrap());
+    }
+
+    #[test]
+    fn default_model_downloads_without_a_checksum_from_the_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = fake_model();
+        let published = dir.path().join("published.bin");
+        std::fs::write(&published, &model).unwrap();
+        let url = crate::download::test_support::serve(model.clone(), Some(sha256_of_file(&published).unwrap()), 2);
+        let source = ModelSource {
+            mirror_url: Some(url.rsplit_once('/').unwrap().0.to_string()),
+            ..on_demand_source()
+        };
+        let path = dir.path().join(model_file_name(DEFAULT_MODEL));
+        let progress = ProgressReporter::new(ProgressStep::Transcribe, |_| {});
+
+        resolve_model_file(DEFAULT_MODEL, &path, &source, &progress, &CancelToken::default()).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), model);
+        assert!(verify_installed_model(DEFAULT_MODEL, &path).unwrap());
+    }
+}
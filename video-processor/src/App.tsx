@@ -1,6 +1,8 @@
 import { useState, useEffect } from 'react'
 import './App.css'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { runJob, cancelJob } from './jobs'
 import { open, save } from '@tauri-apps/plugin-dialog'
 
 // Components
@@ -11,7 +13,10 @@ import VideoPreview from './components/VideoPreview'
 import ProgressBar from './components/ProgressBar'
 
 // Types
-import { CutPoint, VideoProcessingOptions, Transcript } from './types'
+import { CutPoint, CutAnalysisOptions, VideoProcessingOptions, Transcript, ProgressEvent, ProgressStep } from './types'
+
+// Pauses longer than a second split the transcript into clips
+const cutAnalysisOptions: CutAnalysisOptions = { pauseThresholdSecs: 1.0 }
 
 function App() {
   const [videoPath, setVideoPath] = useState<string | null>(null)
@@ -23,6 +28,9 @@ function App() {
   const [processedVideoPath, setProcessedVideoPath] = useState<string | null>(null)
   const [currentStep, setCurrentStep] = useState<number>(0)
   const [progress, setProgress] = useState<number>(0)
+  const [etaSecs, setEtaSecs] = useState<number | null>(null)
+  const [progressStep, setProgressStep] = useState<ProgressStep | null>(null)
+  const [activeJobId, setActiveJobId] = useState<string | null>(null)
   const [error, setError] = useState<string | null>(null)
   const [applyZoomEffects, setApplyZoomEffects] = useState<boolean>(false)
 
@@ -62,15 +70,11 @@ function App() {
 
     try {
       setProgress(0)
-      const progressInterval = setInterval(() => {
-        setProgress(prev => Math.min(prev + 5, 90))
-      }, 500)
 
-      const result = await invoke<string>('extract_audio_from_video', { videoPath })
+      const result = await runJob('extract_audio_from_video', { videoPath }, setActiveJobId)
       setAudioPath(result)
       setCurrentStep(2)
       
-      clearInterval(progressInterval)
       setProgress(100)
       
       // Automatically move to next step after a short delay
@@ -80,6 +84,8 @@ function App() {
       }, 1000)
     } catch (err) {
       setError(`Error extracting audio: ${err}`)
+    } finally {
+      setActiveJobId(null)
     }
   }
 
@@ -89,18 +95,14 @@ function App() {
 
     try {
       setProgress(0)
-      const progressInterval = setInterval(() => {
-        setProgress(prev => Math.min(prev + 2, 90))
-      }, 500)
 
-      const result = await invoke<string>('transcribe_audio_file', { audioPath })
+      const result = await runJob('transcribe_audio_file', { audioPath }, setActiveJobId)
       setTranscriptPath(result)
       
       // Load transcript content
       const transcriptContent = await fetch(`file://${result}`).then(res => res.json())
       setTranscript(transcriptContent)
       
-      clearInterval(progressInterval)
       setProgress(100)
       
       // Automatically move to next step after a short delay
@@ -110,47 +112,25 @@ function App() {
       }, 1000)
     } catch (err) {
       setError(`Error transcribing audio: ${err}`)
+    } finally {
+      setActiveJobId(null)
     }
   }
 
   // Analyze transcript for cut points
   const analyzeTranscript = async () => {
-    if (!transcript) return
-
-    // For now, we'll use a simple algorithm to find natural breaks
-    const segments = transcript.segments
-    const newCutPoints: CutPoint[] = []
-    let currentStart = 0
+    if (!transcriptPath) return
 
-    for (let i = 1; i < segments.length; i++) {
-      const prevSegment = segments[i - 1]
-      const currSegment = segments[i]
-      
-      // If there's a pause of more than 1 second, consider it a cut point
-      const pauseDuration = currSegment.start - prevSegment.end
-      if (pauseDuration > 1.0) {
-        newCutPoints.push({
-          startTime: currentStart,
-          endTime: prevSegment.end,
-          description: `Segment ${newCutPoints.length + 1}`
-        })
-        
-        currentStart = currSegment.start
-      }
-    }
-    
-    // Add the final segment
-    if (segments.length > 0) {
-      const lastSegment = segments[segments.length - 1]
-      newCutPoints.push({
-        startTime: currentStart,
-        endTime: lastSegment.end,
-        description: `Segment ${newCutPoints.length + 1}`
+    try {
+      const newCutPoints = await invoke<CutPoint[]>('analyze_transcript_for_cuts', {
+        transcriptPath,
+        options: cutAnalysisOptions
       })
+      setCutPoints(newCutPoints)
+      setCurrentStep(4)
+    } catch (err) {
+      setError(`Error analyzing transcript: ${err}`)
     }
-
-    setCutPoints(newCutPoints)
-    setCurrentStep(4)
   }
 
   // Process video
@@ -170,9 +150,6 @@ function App() {
       setOutputPath(savePath)
 
       setProgress(0)
-      const progressInterval = setInterval(() => {
-        setProgress(prev => Math.min(prev + 1, 90))
-      }, 500)
 
       const options: VideoProcessingOptions = {
         outputPath: savePath,
@@ -180,14 +157,13 @@ function App() {
         applyZoomEffects
       }
 
-      const result = await invoke<string>('process_video_file', { 
-        videoPath, 
+      const result = await runJob('process_video_file', {
+        videoPath,
         transcriptPath,
         options
-      })
+      }, setActiveJobId)
       
       setProcessedVideoPath(result)
-      clearInterval(progressInterval)
       setProgress(100)
       
       // Move to final step
@@ -196,6 +172,19 @@ function App() {
       }, 1000)
     } catch (err) {
       setError(`Error processing video: ${err}`)
+    } finally {
+      setActiveJobId(null)
+    }
+  }
+
+  // Cancel the job that is currently running
+  const cancelActiveJob = async () => {
+    if (!activeJobId) return
+
+    try {
+      await cancelJob(activeJobId)
+    } catch (err) {
+      setError(`Error cancelling job: ${err}`)
     }
   }
 
@@ -226,6 +215,18 @@ function App() {
     setCutPoints(newCutPoints)
   }
 
+  // Follow progress events emitted by the backend
+  useEffect(() => {
+    const unlisten = listen<ProgressEvent>('progress', event => {
+      setProgress(event.payload.percent)
+      setEtaSecs(event.payload.etaSecs)
+      setProgressStep(event.payload.step)
+    })
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [])
+
   // Effect to automatically proceed with steps
   useEffect(() => {
     if (currentStep === 1 && videoPath) {
@@ -264,11 +265,22 @@ function App() {
         )}
 
         {currentStep > 0 && currentStep < 5 && progress > 0 && (
-          <ProgressBar progress={progress} />
+          <ProgressBar
+            progress={progress}
+            etaSecs={etaSecs}
+            label={progressStep === 'downloadModel' ? 'Downloading Whisper model' : undefined}
+          />
+        )}
+
+        {activeJobId && (
+          <button className="cancel-button" onClick={cancelActiveJob}>Cancel</button>
         )}
 
         {currentStep === 3 && transcript && (
-          <TranscriptViewer transcript={transcript} />
+          <>
+            <TranscriptViewer transcript={transcript} />
+            <button className="process-button" onClick={analyzeTranscript}>Find Cut Points</button>
+          </>
         )}
 
         {currentStep === 4 && (
@@ -2,9 +2,11 @@ import React from 'react';
 
 interface ProgressBarProps {
   progress: number;
+  etaSecs?: number | null;
+  label?: string;
 }
 
-const ProgressBar: React.FC<ProgressBarProps> = ({ progress }) => {
+const ProgressBar: React.FC<ProgressBarProps> = ({ progress, etaSecs, label }) => {
   return (
     <div className="progress-container">
       <div className="progress-bar">
@@ -14,7 +16,9 @@ const ProgressBar: React.FC<ProgressBarProps> = ({ progress }) => {
         />
       </div>
       <div className="progress-text">
+        {label && `${label}: `}
         {Math.round(progress)}%
+        {etaSecs != null && progress < 100 && ` (about ${Math.ceil(etaSecs)}s left)`}
       </div>
     </div>
   );
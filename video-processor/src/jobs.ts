@@ -0,0 +1,42 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { JobFinished } from './types'
+
+// Start a backend job and resolve with its result once the job-finished event arrives
+export async function runJob(
+  command: string,
+  args: Record<string, unknown>,
+  onStart?: (jobId: string) => void
+): Promise<string> {
+  const finished = new Map<string, JobFinished>()
+  let waiting: { jobId: string; resolve: (event: JobFinished) => void } | null = null
+
+  // Listen before invoking so a job that finishes immediately isn't missed
+  const unlisten = await listen<JobFinished>('job-finished', event => {
+    if (waiting && waiting.jobId === event.payload.jobId) {
+      waiting.resolve(event.payload)
+    } else {
+      finished.set(event.payload.jobId, event.payload)
+    }
+  })
+
+  try {
+    const jobId = await invoke<string>(command, args)
+    onStart?.(jobId)
+
+    const event = finished.get(jobId) ?? await new Promise<JobFinished>(resolve => {
+      waiting = { jobId, resolve }
+    })
+
+    if (event.error !== null || event.result === null) {
+      throw event.error ?? 'Job finished without a result'
+    }
+    return event.result
+  } finally {
+    unlisten()
+  }
+}
+
+export async function cancelJob(jobId: string): Promise<void> {
+  await invoke('cancel_job', { jobId })
+}
@@ -10,13 +10,173 @@ export interface VideoProcessingOptions {
   applyZoomEffects: boolean;
 }
 
+export interface TranscriptWord {
+  text: string;
+  start: number;
+  end: number;
+  probability: number;
+}
+
 export interface TranscriptSegment {
   start: number;
   end: number;
   text: string;
+  words?: TranscriptWord[];
 }
 
 export interface Transcript {
   segments: TranscriptSegment[];
   text: string;
-} 
\ No newline at end of file
+  language?: string | null;
+  language_probability?: number | null;
+  translated?: boolean;
+}
+
+/** 'mix' averages all channels; { index: 0 } picks the left channel of a stereo file */
+export type ChannelSelection = 'mix' | { index: number };
+
+export interface TranscriptionOptions {
+  language?: string | null;
+  translate?: boolean;
+  chunkSecs?: number | null;
+  chunkOverlapSecs?: number;
+  channel?: ChannelSelection;
+  decoding?: DecodingPreset | DecodingOptions;
+  threads?: number | null;
+  maxSegmentChars?: number | null;
+  initialPrompt?: string | null;
+  vocabulary?: Vocabulary;
+}
+
+export type ReplacementRule =
+  | { kind: 'literal'; find: string; replace: string; caseSensitive?: boolean }
+  | { kind: 'regex'; pattern: string; replace: string };
+
+/** Per-project names fed to Whisper as prompt, plus fixes applied to the transcript */
+export interface Vocabulary {
+  terms?: string[];
+  rules?: ReplacementRule[];
+}
+
+export type DecodingPreset = 'fast' | 'accurate';
+
+export interface DecodingOptions {
+  beamSize?: number | null;
+  bestOf?: number;
+  temperature?: number;
+  temperatureIncrement?: number;
+  suppressBlank?: boolean;
+  suppressNonSpeech?: boolean;
+}
+
+export type ProgressStep = 'extractAudio' | 'transcribe' | 'render' | 'downloadModel';
+
+export interface ProgressEvent {
+  jobId: string | null;
+  step: ProgressStep;
+  percent: number;
+  elapsedSecs: number;
+  etaSecs: number | null;
+}
+
+export interface JobFinished {
+  jobId: string;
+  result: string | null;
+  error: string | null;
+  cancelled: boolean;
+}
+
+export interface WhisperModelInfo {
+  id: string;
+  fileName: string;
+  sizeMb: number | null;
+  englishOnly: boolean;
+  quantization: string | null;
+  installed: boolean;
+  known: boolean;
+}
+
+export interface ModelSource {
+  mirrorUrl?: string;
+  sha256?: string;
+  trustServerChecksum?: boolean;
+}
+
+export type SubtitleFormat = 'srt' | 'vtt' | 'ass' | 'ssa' | 'ttml' | 'txt';
+
+/** Readability limits used to re-split a transcript into caption cues on export */
+export interface CaptionOptions {
+  maxCharsPerLine?: number;
+  maxLines?: number;
+  maxCharsPerSecond?: number;
+  minDurationSecs?: number;
+  maxDurationSecs?: number;
+}
+
+export interface CutAnalysisOptions {
+  pauseThresholdSecs?: number;
+  minClipSecs?: number;
+  maxClipSecs?: number | null;
+  paddingBeforeSecs?: number | null;
+  paddingAfterSecs?: number;
+  mergeGapSecs?: number;
+}
+
+export interface SilenceOptions {
+  thresholdDb?: number;
+  hysteresisDb?: number;
+  minSilenceSecs?: number;
+  windowSecs?: number;
+  channel?: ChannelSelection;
+}
+
+export interface FillerOptions {
+  fillers?: Record<string, string[]>;
+  repeatable?: Record<string, string[]>;
+  language?: string | null;
+  removeRepetitions?: boolean;
+  paddingSecs?: number;
+}
+
+export interface RetakeOptions {
+  similarityThreshold?: number;
+  minWords?: number;
+  paddingSecs?: number;
+  keepSentences?: number[];
+}
+
+export interface RejectedTake {
+  sentence: number;
+  start: number;
+  end: number;
+  text: string;
+  similarity: number;
+}
+
+export interface RetakeAnalysis {
+  cutPoints: CutPoint[];
+  rejectedTakes: RejectedTake[];
+}
+
+export type Deletion =
+  | { kind: 'segments'; first: number; last: number }
+  | { kind: 'words'; segment: number; firstWord: number; lastWord: number };
+
+export interface TextCutOptions {
+  paddingSecs?: number;
+  minKeepSecs?: number;
+  mediaDurationSecs?: number | null;
+}
+
+export type TranscriptEdit =
+  | { kind: 'edit_text'; segment: number; text: string }
+  | { kind: 'split'; segment: number; word: number }
+  | { kind: 'merge'; segment: number }
+  | { kind: 'set_timing'; segment: number; start: number; end: number };
+
+export interface TranscriptVersion {
+  id: number;
+  description: string;
+  createdAt: number;
+  current: boolean;
+}